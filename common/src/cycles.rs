@@ -0,0 +1,194 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86 as arch;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64 as arch;
+
+/// The clock behind `rdtsc()`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    // The time-stamp counter of the CPU.
+    Tsc,
+
+    // CLOCK_MONOTONIC in nano-seconds; when the TSC doesn't tick at a constant rate.
+    Monotonic,
+}
+
+// The clock picked at the first use.
+struct Clock {
+    // The clock behind `rdtsc()`.
+    source: Source,
+
+    // The frequency of the clock.
+    cycles_per_second: u64,
+
+    // The start of CLOCK_MONOTONIC readings.
+    epoch: Instant,
+}
+
+static CLOCK: OnceLock<Clock> = OnceLock::new();
+
+/// Perform once-only overall initialization for the cycles module; pick the clock and calibrate
+/// its frequency. This method is invoked automatically during initialization.
+fn init() -> Clock {
+    let mut clock = Clock {
+        source: Source::Monotonic,
+        cycles_per_second: 1_000_000_000,
+        epoch: Instant::now(),
+    };
+    if invariant_tsc() {
+        if let Some(cycles_per_second) = calibrate() {
+            clock.source = Source::Tsc;
+            clock.cycles_per_second = cycles_per_second;
+        }
+    }
+    clock
+}
+
+// The clock, picked and calibrated at the first call.
+fn clock() -> &'static Clock {
+    CLOCK.get_or_init(init)
+}
+
+/// This function tells if the TSC ticks at a constant rate, whatever the power state of the
+/// core; CPUID leaf 0x80000007, EDX bit 8.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn invariant_tsc() -> bool {
+    if arch::__cpuid(0x8000_0000).eax < 0x8000_0007 {
+        return false;
+    }
+    arch::__cpuid(0x8000_0007).edx & (1 << 8) != 0
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+pub fn invariant_tsc() -> bool {
+    false
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn tsc() -> u64 {
+    unsafe { arch::_rdtsc() }
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn tsc() -> u64 {
+    0
+}
+
+/// Compute the frequency of the TSC: take parallel readings of the TSC and CLOCK_MONOTONIC (the
+/// clock behind `Instant` on Linux), and after 10ms take the ratio between them. We could get
+/// interrupted between the two readings, so repeat until two successive results are within 0.1%
+/// of each other. Stolen from the RAMCloud code base. Thanks, John.
+///
+/// # Return
+/// The TSC cycles per second; None if the results never settle.
+fn calibrate() -> Option<u64> {
+    let mut old_cycles_per_second = 0.0;
+    for _ in 0..100 {
+        let start_time = Instant::now();
+        let start_cycles = tsc();
+        let mut elapsed = start_time.elapsed();
+        while elapsed < Duration::from_millis(10) {
+            elapsed = start_time.elapsed();
+        }
+        let cycles_per_second = (tsc() - start_cycles) as f64 / elapsed.as_secs_f64();
+
+        let delta = cycles_per_second / 1000.0;
+        if (old_cycles_per_second - cycles_per_second).abs() < delta {
+            return Some(cycles_per_second as u64);
+        }
+        old_cycles_per_second = cycles_per_second;
+    }
+    None
+}
+
+/// Return the clock behind `rdtsc()`; the TSC, unless it isn't invariant or can't be calibrated.
+pub fn source() -> Source {
+    clock().source
+}
+
+/// Return the CPU cycles per second for the executing processor.
+///
+/// # Return
+///
+/// Number of CPU cycles per second; 1e9 if the clock is CLOCK_MONOTONIC.
+pub fn cycles_per_second() -> u64 {
+    clock().cycles_per_second
+}
+
+/// Return a 64-bit timestamp using the rdtsc instruction; or the nano-seconds from
+/// CLOCK_MONOTONIC if the TSC can't be used.
+pub fn rdtsc() -> u64 {
+    match source() {
+        Source::Tsc => tsc(),
+        Source::Monotonic => clock().epoch.elapsed().as_nanos() as u64,
+    }
+}
+
+/// Converts the number of CPU cycles to seconds.
+///
+/// # Arguments
+/// *`cycles`: Number of CPU cycles.
+///
+/// # Return
+/// Number of seconds corresponding to the given CPU cycles.
+pub fn to_seconds(cycles: u64) -> f64 {
+    cycles as f64 / cycles_per_second() as f64
+}
+
+/// Converts the number of CPU cycles to nano-seconds.
+pub fn to_nanos(cycles: u64) -> u64 {
+    (cycles as u128 * 1_000_000_000 / cycles_per_second() as u128) as u64
+}
+
+/// Converts nano-seconds to the number of CPU cycles.
+pub fn from_nanos(nanos: u64) -> u64 {
+    (nanos as u128 * cycles_per_second() as u128 / 1_000_000_000) as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_init() {
+        assert!(cycles_per_second() > 100000000);
+        assert!(cycles_per_second() < 10000000000);
+        if source() == Source::Monotonic {
+            assert_eq!(cycles_per_second(), 1000000000);
+        }
+    }
+
+    #[test]
+    fn test_rdtsc_sanity() {
+        let start = rdtsc();
+        thread::sleep(Duration::from_millis(100));
+        let stop = rdtsc();
+        assert!((to_seconds(stop - start) - 0.1).abs() < 0.01);
+        assert!((to_nanos(stop - start) as f64 / 1e8 - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_nanos() {
+        assert_eq!(to_nanos(from_nanos(1000000000)), 1000000000);
+        assert_eq!(from_nanos(1000000000), cycles_per_second());
+    }
+}
@@ -0,0 +1,302 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use std::cmp::{max, min};
+
+/// The default precision; the values are kept within 1/128 (0.8%) of their real value.
+pub const DEFAULT_PRECISION: u32 = 7;
+
+/// A log-bucketed histogram of u64 values, e.g. latencies in CPU cycles. Values below
+/// 2^precision are counted exactly; above that, each power of two is split in 2^precision
+/// buckets, which bounds the relative error by 2^-precision. The memory is proportional to the
+/// logarithm of the ratio of the largest to the smallest value, and not to the number of values;
+/// a histogram with few values, e.g. one of many tenants, stays small.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Histogram {
+    // The number of significant bits kept for each value.
+    precision: u32,
+
+    // The number of values in each bucket, from the bucket of the smallest to the bucket of the
+    // largest value.
+    counts: Vec<u64>,
+
+    // The bucket of `counts[0]`.
+    #[serde(default)]
+    first: usize,
+
+    // The number of values recorded.
+    count: u64,
+
+    // The smallest and the largest value recorded.
+    min: u64,
+    max: u64,
+
+    // The sum of the values recorded, for the mean.
+    sum: u128,
+}
+
+impl Default for Histogram {
+    fn default() -> Histogram {
+        Histogram::new(DEFAULT_PRECISION)
+    }
+}
+
+impl Histogram {
+    /// This method creates an empty histogram.
+    ///
+    /// # Arguments
+    /// `precision`: The number of significant bits kept for each value; between 1 and 16.
+    pub fn new(precision: u32) -> Histogram {
+        assert!((1..=16).contains(&precision), "precision must be in 1..=16");
+        Histogram {
+            precision,
+            counts: Vec::new(),
+            first: 0,
+            count: 0,
+            min: u64::MAX,
+            max: 0,
+            sum: 0,
+        }
+    }
+
+    // The index of the bucket for the value.
+    fn bucket(&self, value: u64) -> usize {
+        let p = self.precision;
+        if value < (1 << p) {
+            return value as usize;
+        }
+        let shift = 63 - value.leading_zeros() - p;
+        ((shift as u64) << p) as usize + (value >> shift) as usize
+    }
+
+    // The smallest and the largest value which fall in the bucket.
+    fn bounds(&self, bucket: usize) -> (u64, u64) {
+        let p = self.precision;
+        if bucket < (1 << p) {
+            return (bucket as u64, bucket as u64);
+        }
+        let shift = (bucket >> p) as u32 - 1;
+        let mantissa = (bucket - ((shift as usize) << p)) as u64;
+        let low = mantissa << shift;
+        (low, low + ((1u64 << shift) - 1))
+    }
+
+    // Extend the buckets in `counts` to cover the buckets from `low` to `high`.
+    fn cover(&mut self, low: usize, high: usize) {
+        if self.counts.is_empty() {
+            self.first = low;
+        }
+        if low < self.first {
+            let prepend = self.first - low;
+            self.counts.splice(0..0, std::iter::repeat_n(0, prepend));
+            self.first = low;
+        }
+        if high >= self.first + self.counts.len() {
+            self.counts.resize(high - self.first + 1, 0);
+        }
+    }
+
+    /// This method adds a value to the histogram.
+    pub fn record(&mut self, value: u64) {
+        self.record_n(value, 1);
+    }
+
+    /// This method adds a value `n` times to the histogram.
+    pub fn record_n(&mut self, value: u64, n: u64) {
+        if n == 0 {
+            return;
+        }
+        let bucket = self.bucket(value);
+        self.cover(bucket, bucket);
+        self.counts[bucket - self.first] += n;
+        self.count += n;
+        self.sum += value as u128 * n as u128;
+        self.min = min(self.min, value);
+        self.max = max(self.max, value);
+    }
+
+    /// This method adds all the values of another histogram to this one, e.g. to combine the
+    /// histograms of several cores or threads.
+    ///
+    /// # Arguments
+    /// `other`: The histogram to add; it must have the same precision.
+    pub fn merge(&mut self, other: &Histogram) {
+        assert_eq!(self.precision, other.precision, "can't merge histograms");
+        if other.counts.is_empty() {
+            return;
+        }
+        self.cover(other.first, other.first + other.counts.len() - 1);
+        let offset = other.first - self.first;
+        for (i, n) in other.counts.iter().enumerate() {
+            self.counts[offset + i] += n;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = min(self.min, other.min);
+        self.max = max(self.max, other.max);
+    }
+
+    /// This method removes all the values from the histogram.
+    pub fn clear(&mut self) {
+        *self = Histogram::new(self.precision);
+    }
+
+    /// The number of values recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The smallest value recorded; zero if the histogram is empty.
+    pub fn min(&self) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        self.min
+    }
+
+    /// The largest value recorded; zero if the histogram is empty.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// The mean of the values recorded; zero if the histogram is empty.
+    pub fn mean(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        self.sum as f64 / self.count as f64
+    }
+
+    /// This method computes a quantile with the nearest-rank method, i.e. the smallest value
+    /// such that at least `q * count` values are less than or equal to it.
+    ///
+    /// # Arguments
+    /// `q`: The quantile, between 0 and 1; e.g. 0.5 for the median and 0.99 for the tail.
+    ///
+    /// # Return
+    /// The middle of the bucket holding the quantile, within `[min, max]`; zero if the
+    /// histogram is empty.
+    pub fn value_at_quantile(&self, q: f64) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+
+        let rank = max((q * self.count as f64).ceil() as u64, 1);
+        let mut seen = 0;
+        for (i, n) in self.counts.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                let (low, high) = self.bounds(self.first + i);
+                let value = low + (high - low) / 2;
+                return min(max(value, self.min), self.max);
+            }
+        }
+        self.max
+    }
+
+    /// The median of the values recorded.
+    pub fn median(&self) -> u64 {
+        self.value_at_quantile(0.5)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_quantiles() {
+        let mut histogram = Histogram::new(7);
+        for value in 1..=1000 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.median(), 500);
+        assert_eq!(histogram.min(), 1);
+        assert_eq!(histogram.max(), 1000);
+        assert!((histogram.mean() - 500.5).abs() < 1e-9);
+
+        let p99 = histogram.value_at_quantile(0.99) as f64;
+        assert!((p99 / 990.0 - 1.0).abs() <= 1.0 / 128.0);
+
+        // The median of an even number of values is the lower middle one.
+        let mut even = Histogram::new(7);
+        for value in &[10, 20, 30, 40] {
+            even.record(*value);
+        }
+        assert_eq!(even.median(), 20);
+        assert_eq!(Histogram::new(7).median(), 0);
+    }
+
+    #[test]
+    fn test_buckets() {
+        let histogram = Histogram::new(4);
+        let mut last = 0;
+        for value in (0..100000).chain(vec![u64::MAX - 1, u64::MAX]) {
+            let bucket = histogram.bucket(value);
+            let (low, high) = histogram.bounds(bucket);
+            assert!(
+                low <= value && value <= high,
+                "{} in {}..{}",
+                value,
+                low,
+                high
+            );
+            assert!(bucket >= last);
+            last = bucket;
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let (mut a, mut b, mut all) = (Histogram::new(7), Histogram::new(7), Histogram::new(7));
+        for value in 0..5000u64 {
+            let value = value * value;
+            if value % 3 == 0 {
+                a.record(value);
+            } else {
+                b.record(value);
+            }
+            all.record(value);
+        }
+        a.merge(&b);
+        assert_eq!(a, all);
+    }
+
+    #[test]
+    fn test_sparse() {
+        // Only the buckets between the smallest and the largest value are kept.
+        let mut histogram = Histogram::new(7);
+        histogram.record(1_000_000);
+        assert_eq!(histogram.counts.len(), 1);
+        histogram.record(900_000);
+        histogram.record(1_100_000);
+        assert!(histogram.counts.len() < 64);
+        assert_eq!(histogram.median(), 1_000_000 - 1_000_000 % 4096 + 2047);
+
+        let mut low = Histogram::new(7);
+        low.record(10);
+        low.merge(&histogram);
+        assert_eq!(low.min(), 10);
+        assert_eq!(low.value_at_quantile(0.25), 10);
+        assert_eq!(low.count(), 4);
+        low.merge(&Histogram::new(7));
+        assert_eq!(low.count(), 4);
+    }
+}
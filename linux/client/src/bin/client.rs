@@ -19,6 +19,8 @@ extern crate core_affinity;
 use client::config::ClientConfig;
 use client::cycles;
 
+use common::histogram::Histogram;
+
 use rand::distributions::{Distribution, Uniform};
 use rand::prelude::*;
 use rand::rngs::ThreadRng;
@@ -115,9 +117,9 @@ struct Receiver {
     // The total number of responses received so far.
     recvd: u64,
 
-    // Vector of sampled request latencies. Required to calculate distributions once all responses
-    // have been received.
-    latencies: Vec<u64>,
+    // Histogram of sampled request latencies. Required to calculate distributions once all
+    // responses have been received.
+    latencies: Histogram,
 
     // If true, this receiver will make latency measurements.
     master: bool,
@@ -133,7 +135,7 @@ impl Receiver {
             responses: config.num_resps,
             start: cycles::rdtsc(),
             recvd: 0,
-            latencies: Vec::with_capacity(config.num_resps as usize),
+            latencies: Histogram::new(config.histogram_precision),
             master: master,
             stop: 0,
         }
@@ -155,7 +157,7 @@ impl Receiver {
 
                     // Take latency measurement after warmup; say after 2M responses.
                     if self.recvd > 2 * 1000 * 1000 && self.master {
-                        self.latencies.push(cycles::rdtsc() - timestamp);
+                        self.latencies.record(cycles::rdtsc() - timestamp);
                         if self.recvd % 1000000 == 0 {
                             println!("Recvd {} responses", self.recvd);
                         }
@@ -182,18 +184,8 @@ impl Drop for Receiver {
 
         // Calculate & print median & tail latency only on the master thread.
         if self.master {
-            self.latencies.sort();
-
-            let m;
-            let t = self.latencies[(self.latencies.len() * 99) / 100];
-            match self.latencies.len() % 2 {
-                0 => {
-                    let n = self.latencies.len();
-                    m = (self.latencies[n / 2] + self.latencies[(n / 2) + 1]) / 2;
-                }
-
-                _ => m = self.latencies[self.latencies.len() / 2],
-            }
+            let m = self.latencies.median();
+            let t = self.latencies.value_at_quantile(0.99);
 
             println!(
                 ">>> {} {}",
@@ -1,5 +1,4 @@
-#![feature(llvm_asm, integer_atomics)]
-
+extern crate common;
 extern crate serde;
 extern crate serde_aux;
 #[macro_use]
@@ -9,5 +8,5 @@ extern crate toml;
 /// This module is used for parsing the client configuration file.
 pub mod config;
 
-/// This module contains the CPU cycles related functionality; rdtsc() etc.
-pub mod cycles;
+/// The CPU cycles related functionality, shared with the simulator; rdtsc() etc.
+pub use common::cycles;
@@ -12,6 +12,8 @@ use std::sync::Arc;
 use client::config;
 use client::*;
 
+use common::histogram::Histogram;
+
 use rand::distributions::{Distribution, Uniform};
 use rand::prelude::*;
 use rand::rngs::ThreadRng;
@@ -113,9 +115,9 @@ where
     // The total number of responses received so far.
     recvd: u64,
 
-    // Vector of sampled request latencies. Required to calculate distributions once all responses
-    // have been received.
-    latencies: Vec<u64>,
+    // Histogram of sampled request latencies. Required to calculate distributions once all
+    // responses have been received.
+    latencies: Histogram,
 
     // If true, this receiver will make latency measurements.
     master: bool,
@@ -134,7 +136,7 @@ where
             responses: config.num_resps,
             start: 0,
             recvd: 0,
-            latencies: Vec::with_capacity(config.num_resps as usize),
+            latencies: Histogram::new(config.histogram_precision),
             master: master,
             stop: 0,
         }
@@ -160,7 +162,7 @@ where
                         let time = packet.get_payload().split_at(8).0;
                         buf.copy_from_slice(time);
                         let timestamp = u64::from_le_bytes(buf);
-                        self.latencies.push(cycles::rdtsc() - timestamp);
+                        self.latencies.record(cycles::rdtsc() - timestamp);
                     } else {
                         println!("Malformed Response!!!");
                     }
@@ -203,18 +205,8 @@ where
     fn drop(&mut self) {
         // Calculate & print median & tail latency only on the master thread.
         if self.master {
-            self.latencies.sort();
-
-            let m;
-            let t = self.latencies[(self.latencies.len() * 99) / 100];
-            match self.latencies.len() % 2 {
-                0 => {
-                    let n = self.latencies.len();
-                    m = (self.latencies[n / 2] + self.latencies[(n / 2) + 1]) / 2;
-                }
-
-                _ => m = self.latencies[self.latencies.len() / 2],
-            }
+            let m = self.latencies.median();
+            let t = self.latencies.value_at_quantile(0.99);
 
             println!(
                 "Throughput {}, Median(ns) {}, Tail(ns) {}",
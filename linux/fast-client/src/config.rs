@@ -18,6 +18,8 @@ use std::fmt;
 use std::fs::File;
 use std::io::Read;
 
+use common::histogram;
+
 use super::e2d2::headers::*;
 use super::toml;
 
@@ -79,6 +81,14 @@ pub struct ClientConfig {
 
     // The req rate per second.
     pub req_rate: u64,
+
+    // The number of significant bits kept for each latency; between 1 and 16.
+    #[serde(default = "histogram_precision")]
+    pub histogram_precision: u32,
+}
+
+fn histogram_precision() -> u32 {
+    histogram::DEFAULT_PRECISION
 }
 
 impl ClientConfig {
@@ -88,12 +98,21 @@ impl ClientConfig {
 
         let _ = File::open(filename).and_then(|mut file| file.read_to_string(&mut contents));
 
-        match toml::from_str(&contents) {
+        let config: ClientConfig = match toml::from_str(&contents) {
             Ok(config) => config,
             Err(e) => {
                 panic!("Failure paring config file {}: {}", filename, e);
             }
+        };
+
+        // Catch a bad precision here, rather than when the first histogram is created.
+        if !(1..=16).contains(&config.histogram_precision) {
+            panic!(
+                "histogram_precision in {} must be between 1 and 16, not {}",
+                filename, config.histogram_precision
+            );
         }
+        config
     }
 
     /// Parse `mac_address` into NetBrick's format or panic if malformed.
@@ -13,9 +13,10 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
-#![feature(asm, integer_atomics, atomic_min_max)]
+#![feature(integer_atomics, atomic_min_max)]
 
 extern crate e2d2;
+extern crate common;
 extern crate serde;
 extern crate serde_aux;
 #[macro_use]
@@ -24,6 +25,6 @@ extern crate packet;
 extern crate toml;
 
 pub mod config;
-pub mod cycles;
+pub use common::cycles;
 pub mod dispatch;
 pub mod setup;
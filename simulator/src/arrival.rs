@@ -0,0 +1,273 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::{Arrival, Config};
+
+use std::f64::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+pub trait ArrivalProcess {
+    /// This method decides when the next request arrives.
+    ///
+    /// # Arguments
+    /// `curr`: The time-stamp, in CPU cycles, at which the last request arrived.
+    ///
+    /// # Return
+    /// The time-stamp, in CPU cycles, at which the next request arrives.
+    fn next_arrival(&mut self, curr: u64) -> u64;
+}
+
+/// This function creates the arrival process selected in the configuration.
+///
+/// # Arguments
+/// `config`: The simulator configuration.
+/// `req_rate`: The mean number of requests per second.
+/// `rng`: The random number generator for the inter-arrival times.
+pub fn new(config: &Config, req_rate: u64, rng: StdRng) -> Box<dyn ArrivalProcess> {
+    let clock = config.clock();
+    let cycles_per_req = clock.cycles_per_second() as f64 / req_rate as f64;
+    match config.arrival {
+        Arrival::Deterministic => Box::new(Deterministic {
+            interval: cycles_per_req,
+            last: 0.0,
+        }),
+
+        Arrival::Poisson => Box::new(Poisson {
+            mean: cycles_per_req,
+            rng,
+        }),
+
+        Arrival::OnOff => {
+            let on = config.on_off.on_time_us;
+            let off = config.on_off.off_time_us;
+            let on_rate = config.on_off.on_factor;
+            let off_rate = (on + off - on_rate * on) / off;
+            Box::new(OnOff {
+                rates: [off_rate / cycles_per_req, on_rate / cycles_per_req],
+                periods: [off * clock.cycles_per_us(), on * clock.cycles_per_us()],
+                on: false,
+                period_end: 0.0,
+                last: 0.0,
+                rng,
+            })
+        }
+
+        Arrival::Diurnal => Box::new(Diurnal {
+            rate: 1.0 / cycles_per_req,
+            amplitude: config.diurnal.amplitude,
+            period: config.diurnal.period_us * clock.cycles_per_us(),
+            last: 0.0,
+            rng,
+        }),
+    }
+}
+
+// Sample an exponentially distributed time with the given mean.
+pub(crate) fn exponential(rng: &mut StdRng, mean: f64) -> f64 {
+    let u: f64 = rng.gen();
+    -(1.0 - u).ln() * mean
+}
+
+/// Requests arrive at a fixed interval.
+pub struct Deterministic {
+    // The time interval between two requests in cycles; less than one if more than one request
+    // arrives in each cycle.
+    interval: f64,
+
+    // The time-stamp of the last arrival; kept as a float to not lose the fractions of cycles.
+    last: f64,
+}
+
+impl ArrivalProcess for Deterministic {
+    // Lookup the `ArrivalProcess` trait for documentation on this method.
+    fn next_arrival(&mut self, _curr: u64) -> u64 {
+        self.last += self.interval;
+        self.last as u64
+    }
+}
+
+/// Requests arrive with exponentially distributed inter-arrival times.
+pub struct Poisson {
+    // The mean time between two requests in cycles.
+    mean: f64,
+
+    // Random number generator.
+    rng: StdRng,
+}
+
+impl ArrivalProcess for Poisson {
+    // Lookup the `ArrivalProcess` trait for documentation on this method.
+    fn next_arrival(&mut self, curr: u64) -> u64 {
+        curr + exponential(&mut self.rng, self.mean) as u64
+    }
+}
+
+/// A two-state Markov-modulated Poisson process; the arrival rate switches between the on and
+/// the off rate after exponentially distributed periods. The mean rate stays `req_rate`.
+pub struct OnOff {
+    // The arrival rate, in requests per cycle, in the off and the on state.
+    rates: [f64; 2],
+
+    // The mean length of the off and the on periods in cycles.
+    periods: [f64; 2],
+
+    // The current state.
+    on: bool,
+
+    // The time-stamp at which the current period ends.
+    period_end: f64,
+
+    // The time-stamp of the last arrival; kept as a float to not lose the fractions of cycles.
+    last: f64,
+
+    // Random number generator.
+    rng: StdRng,
+}
+
+impl ArrivalProcess for OnOff {
+    // Lookup the `ArrivalProcess` trait for documentation on this method.
+    fn next_arrival(&mut self, _curr: u64) -> u64 {
+        let mut time = self.last;
+        loop {
+            if time >= self.period_end {
+                self.on = !self.on;
+                self.period_end = time + exponential(&mut self.rng, self.periods[self.on as usize]);
+            }
+
+            // The process is memoryless; restart the arrival at the end of the period if the
+            // state changes before the request arrives.
+            let rate = self.rates[self.on as usize];
+            if rate > 0.0 {
+                let next = time + exponential(&mut self.rng, 1.0 / rate);
+                if next < self.period_end {
+                    self.last = next;
+                    return next as u64;
+                }
+            }
+            time = self.period_end;
+        }
+    }
+}
+
+/// The arrival rate follows a sine wave around `req_rate`; generated by thinning a Poisson
+/// process at the peak rate.
+pub struct Diurnal {
+    // The mean arrival rate in requests per cycle.
+    rate: f64,
+
+    // The relative amplitude of the rate; between 0 and 1.
+    amplitude: f64,
+
+    // The length of one period in cycles.
+    period: f64,
+
+    // The time-stamp of the last arrival.
+    last: f64,
+
+    // Random number generator.
+    rng: StdRng,
+}
+
+impl ArrivalProcess for Diurnal {
+    // Lookup the `ArrivalProcess` trait for documentation on this method.
+    fn next_arrival(&mut self, _curr: u64) -> u64 {
+        let peak = self.rate * (1.0 + self.amplitude);
+        let mut time = self.last;
+        loop {
+            time += exponential(&mut self.rng, 1.0 / peak);
+            let rate = self.rate * (1.0 + self.amplitude * (2.0 * PI * time / self.period).sin());
+            if self.rng.gen::<f64>() * peak <= rate {
+                self.last = time;
+                return time as u64;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::cycles::Clock;
+    use super::*;
+    use rand::SeedableRng;
+
+    // Check that the mean rate of the process is `req_rate` within 2%.
+    fn check_rate(process: &mut dyn ArrivalProcess, req_rate: u64) {
+        let clock = Clock::default();
+        let num_reqs = 200000;
+        let mut time = 0;
+        for _ in 0..num_reqs {
+            time = process.next_arrival(time);
+        }
+        let rate = num_reqs as f64 / clock.to_seconds(time);
+        assert!((rate / req_rate as f64 - 1.0).abs() < 0.02, "rate {}", rate);
+    }
+
+    #[test]
+    fn test_mean_rate() {
+        let req_rate = 500000;
+        let clock = Clock::default();
+        let cycles_per_req = clock.cycles_per_second() as f64 / req_rate as f64;
+        let rng = || StdRng::seed_from_u64(7);
+
+        check_rate(
+            &mut Deterministic {
+                interval: cycles_per_req,
+                last: 0.0,
+            },
+            req_rate,
+        );
+
+        // More requests than cycles in a second; a few arrive in each cycle.
+        let fast_rate = 4 * clock.cycles_per_second();
+        check_rate(
+            &mut Deterministic {
+                interval: clock.cycles_per_second() as f64 / fast_rate as f64,
+                last: 0.0,
+            },
+            fast_rate,
+        );
+
+        check_rate(
+            &mut Poisson {
+                mean: cycles_per_req,
+                rng: rng(),
+            },
+            req_rate,
+        );
+        check_rate(
+            &mut OnOff {
+                rates: [0.5 / cycles_per_req, 1.5 / cycles_per_req],
+                periods: [clock.cycles_per_us() * 100.0; 2],
+                on: false,
+                period_end: 0.0,
+                last: 0.0,
+                rng: rng(),
+            },
+            req_rate,
+        );
+        check_rate(
+            &mut Diurnal {
+                rate: 1.0 / cycles_per_req,
+                amplitude: 0.5,
+                period: clock.cycles_per_us() * 1000.0,
+                last: 0.0,
+                rng: rng(),
+            },
+            req_rate,
+        );
+    }
+}
@@ -0,0 +1,135 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+extern crate simulator;
+
+use simulator::calibrate;
+use simulator::cycles::{self, Clock};
+
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "Usage: calibrate [OPTIONS]
+
+Options:
+    --out <path>        Path of the cost profile to write (default: host.toml).
+    --iterations <n>    Repetitions of each measurement (default: 100000).
+    --core <n>          Core to run the measurements on (default: 0).
+    --frequency-ghz <f> Frequency of the simulated CPU, which the profile is in (default: 3.0).
+    --help              Print this message.
+
+Measures the process context-switch, signal and pkey_set() costs on this host, and writes a
+cost profile for the simulator. Apply it on top of the default profile, e.g.
+    --set 'cost_model.profiles=[\"Default\", \"host.toml\"]'";
+
+struct Args {
+    // The path of the cost profile to write.
+    out: String,
+
+    // The repetitions of each measurement.
+    iterations: u64,
+
+    // The core to run the measurements on.
+    core: usize,
+
+    // The frequency of the simulated CPU in GHz.
+    frequency_ghz: f64,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        out: "host.toml".to_string(),
+        iterations: 100000,
+        core: 0,
+        frequency_ghz: cycles::DEFAULT_CYCLES_PER_SECOND as f64 / 1e9,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "--out" => args.out = value("--out")?,
+            "--iterations" => {
+                let n = value("--iterations")?;
+                args.iterations = n
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("Invalid number of iterations '{}'", n))?;
+            }
+            "--core" => {
+                let core = value("--core")?;
+                args.core = core
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid core '{}'", core))?;
+            }
+            "--frequency-ghz" => {
+                let f = value("--frequency-ghz")?;
+                args.frequency_ghz = f
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|f| *f > 0.0)
+                    .ok_or_else(|| format!("Invalid frequency '{}'", f))?;
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("Unknown argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    Ok(args)
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args()?;
+    let clock = Clock::from_ghz(args.frequency_ghz);
+    let m = calibrate::measure(args.iterations, args.core)?;
+
+    println!("Host-Clock {:?}", cycles::host::source());
+    println!("Host-Clock(GHz) {:.3}", m.tsc_hz / 1e9);
+    println!("Pipe(cycles) {:.0}", m.pipe_cycles);
+    println!("Context-Switch(cycles) {:.0}", m.context_switch_cycles);
+    println!("Signal(cycles) {:.0}", m.signal_cycles);
+    match m.pkey_set_cycles {
+        Some(cycles) => println!("Pkey-Set(cycles) {:.0}", cycles),
+        None => println!("Pkey-Set(cycles) unsupported"),
+    }
+
+    let profile = toml::to_string(&m.profile(&clock))
+        .map_err(|e| format!("Failure serializing the profile: {}", e))?;
+    let contents = format!(
+        "# Measured with a {:.3} GHz host clock; the costs are in the cycles of the simulated CPU, \
+         at {:.3} GHz.\n\n{}",
+        m.tsc_hz / 1e9,
+        clock.cycles_per_second() as f64 / 1e9,
+        profile
+    );
+    fs::write(&args.out, contents).map_err(|e| format!("Failure writing {}: {}", args.out, e))?;
+    println!("Wrote {}", args.out);
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("calibrate: {}", e);
+        process::exit(1);
+    }
+}
@@ -13,11 +13,152 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
+#[macro_use]
+extern crate log;
 extern crate simulator;
 
+use simulator::config::Config;
 use simulator::cores::Simulator;
+use simulator::sweep::Sweep;
+use simulator::trace;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "Usage: simulator [OPTIONS]
+       simulator sweep <sweep.toml>
+       simulator convert-trace <trace.csv> <trace.bin>
+
+Options:
+    --config <path>     Path of the configuration file (default: config.toml).
+    --set <key=value>   Override a configuration field, e.g. isolation=MpkIsolation.
+    --seed <n>          Seed for the random number generators, at most 2^63 - 1; same as
+                        --set seed=<n>.
+    --out <dir>         Directory to write the configuration and results to.
+    --help              Print this message.
+
+The sweep subcommand runs the simulator for every point of the grid in the sweep file, in
+parallel, and writes one table per series. The convert-trace subcommand converts a CSV request
+trace to the compact binary trace format.";
+
+struct Args {
+    // The path of the configuration file.
+    config: String,
+
+    // The `key=value` overrides for the configuration.
+    overrides: Vec<String>,
+
+    // The directory to write the results to.
+    out: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        config: "config.toml".to_string(),
+        overrides: Vec::new(),
+        out: None,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "--config" => args.config = value("--config")?,
+            "--set" => args.overrides.push(value("--set")?),
+            "--seed" => {
+                // The seed goes through a TOML override, whose integers are signed.
+                let seed = value("--seed")?;
+                let n = seed
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|n| *n <= i64::MAX as u64)
+                    .ok_or_else(|| {
+                        format!(
+                            "Invalid seed '{}'; it must be between 0 and {}",
+                            seed,
+                            i64::MAX
+                        )
+                    })?;
+                args.overrides.push(format!("seed={}", n));
+            }
+            "--out" => args.out = Some(PathBuf::from(value("--out")?)),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("Unknown argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    Ok(args)
+}
+
+// Convert a CSV trace to the binary trace format.
+fn convert_trace(input: &str, output: &str) -> Result<(), String> {
+    let contents =
+        fs::read_to_string(input).map_err(|e| format!("Failure reading {}: {}", input, e))?;
+    let records =
+        trace::parse_csv(&contents).map_err(|e| format!("Failure parsing {}: {}", input, e))?;
+    let binary =
+        trace::to_binary(&records).map_err(|e| format!("Failure converting {}: {}", input, e))?;
+    fs::write(output, binary).map_err(|e| format!("Failure writing {}: {}", output, e))?;
+    info!("Converted {} records", records.len());
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let mut argv = env::args().skip(1);
+    match argv.next().as_deref() {
+        Some("sweep") => {
+            return match (argv.next(), argv.next()) {
+                (Some(filename), None) => Sweep::load(&filename)?.run(),
+                _ => Err(format!("Expected one sweep file\n\n{}", USAGE)),
+            };
+        }
+
+        Some("convert-trace") => {
+            return match (argv.next(), argv.next(), argv.next()) {
+                (Some(input), Some(output), None) => convert_trace(&input, &output),
+                _ => Err(format!("Expected a CSV and a binary trace\n\n{}", USAGE)),
+            };
+        }
+
+        _ => {}
+    }
+
+    let args = parse_args()?;
+    let config = Config::load_with(&args.config, &args.overrides)?;
+
+    if let Some(dir) = &args.out {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failure creating directory {}: {}", dir.display(), e))?;
+
+        // Save the effective configuration next to the results.
+        let filename = dir.join("config.toml");
+        let contents = toml::to_string(&config.to_value()?)
+            .map_err(|e| format!("Failure serializing config: {}", e))?;
+        fs::write(&filename, contents)
+            .map_err(|e| format!("Failure writing {}: {}", filename.display(), e))?;
+    }
+
+    info!("Starting the Simulator with config {:?}\n", config);
+    let mut simulator = Simulator::new(config)?;
+    if let Some(dir) = args.out {
+        simulator.set_out_dir(dir);
+    }
+    simulator.start();
+    Ok(())
+}
 
 fn main() {
     env_logger::init();
-    Simulator::new().start();
+    if let Err(e) = run() {
+        eprintln!("simulator: {}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,141 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::CacheConfig;
+
+use std::collections::VecDeque;
+
+// The state of the tenants in a cache or a TLB; the least recently used tenant is at the front,
+// and loses its state first when the cache is full.
+struct Occupancy {
+    // The size of the cache; in KB for a cache, in pages for a TLB.
+    capacity: f64,
+
+    // The tenants with state in the cache, and the size of their state.
+    resident: VecDeque<(u32, f64)>,
+}
+
+impl Occupancy {
+    fn new(capacity: f64) -> Occupancy {
+        Occupancy {
+            capacity,
+            resident: VecDeque::new(),
+        }
+    }
+
+    // Bring the whole state of the tenant into the cache, evicting the least recently used
+    // tenants to make room; returns how much of it was missing.
+    fn fill(&mut self, tenant: u32, size: f64) -> f64 {
+        let size = size.min(self.capacity);
+        let mut present = 0.0;
+        if let Some(i) = self.resident.iter().position(|(t, _)| *t == tenant) {
+            present = self.resident.remove(i).unwrap().1;
+        }
+
+        let mut used: f64 = self.resident.iter().map(|(_, s)| s).sum();
+        while used + size > self.capacity && !self.resident.is_empty() {
+            let (_, evicted) = self.resident.front_mut().unwrap();
+            let freed = evicted.min(used + size - self.capacity);
+            *evicted -= freed;
+            used -= freed;
+            if *evicted <= 0.0 {
+                self.resident.pop_front();
+            }
+        }
+        self.resident.push_back((tenant, size));
+        size - present
+    }
+
+    // Drop the state of all the tenants.
+    fn flush(&mut self) {
+        self.resident.clear();
+    }
+}
+
+/// The last-level cache and the TLB of one core. A tenant needs its working set in both; what
+/// other tenants evicted since it last ran is missed again, and slows down its next request.
+pub struct CacheModel {
+    // The tenant state in the cache, in KB.
+    cache: Occupancy,
+
+    // The tenant state in the TLB, in pages.
+    tlb: Occupancy,
+
+    // The working set of each tenant in KB.
+    working_set_kb: f64,
+
+    // The CPU cycles to bring a 64-byte line of a working set into the cache.
+    line_miss_cycles: u64,
+
+    // The CPU cycles to walk the page tables for a page missing in the TLB.
+    tlb_miss_cycles: u64,
+
+    // If the TLB entries are tagged with a PCID, so they survive a page-table switch.
+    pcid: bool,
+}
+
+impl CacheModel {
+    pub fn new(config: &CacheConfig) -> CacheModel {
+        CacheModel {
+            cache: Occupancy::new(config.capacity_kb),
+            tlb: Occupancy::new(config.tlb_entries as f64),
+            working_set_kb: config.working_set_kb,
+            line_miss_cycles: config.line_miss_cycles,
+            tlb_miss_cycles: config.tlb_miss_cycles,
+            pcid: config.pcid,
+        }
+    }
+
+    /// This method brings the working set of the tenant, which is about to run, into the cache
+    /// and the TLB.
+    ///
+    /// # Arguments
+    /// `tenant`: The tenant-id of the next tenant to run.
+    /// `new_page_table`: True if the core switched to the page table of another address space;
+    ///                   this flushes the TLB without PCID.
+    ///
+    /// # Return
+    /// The CPU cycles spent on the cache and TLB misses.
+    pub fn warm(&mut self, tenant: u32, new_page_table: bool) -> u64 {
+        if new_page_table && !self.pcid {
+            self.tlb.flush();
+        }
+
+        let lines = self.cache.fill(tenant, self.working_set_kb) * 1024.0 / 64.0;
+        let pages = self.tlb.fill(tenant, self.working_set_kb / 4.0);
+        (lines * self.line_miss_cycles as f64 + pages * self.tlb_miss_cycles as f64) as u64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_occupancy() {
+        // Two tenants fit; a third evicts the least recently used one, partly.
+        let mut cache = Occupancy::new(10.0);
+        assert_eq!(cache.fill(1, 4.0), 4.0);
+        assert_eq!(cache.fill(2, 4.0), 4.0);
+        assert_eq!(cache.fill(1, 4.0), 0.0);
+        assert_eq!(cache.fill(3, 4.0), 4.0);
+        assert_eq!(cache.fill(2, 4.0), 2.0);
+
+        // A working set larger than the cache misses the whole cache.
+        assert_eq!(cache.fill(4, 20.0), 10.0);
+        cache.flush();
+        assert_eq!(cache.fill(4, 20.0), 10.0);
+    }
+}
@@ -0,0 +1,400 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::Isolation;
+use super::consts;
+use super::costs::{CostModel, MechanismCost, Profile};
+use super::cycles::{host, Clock};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use libc::{c_int, c_uint};
+
+extern "C" {
+    // From glibc 2.27; reads and writes the PKRU register.
+    fn pkey_set(key: c_int, rights: c_uint) -> c_int;
+}
+
+/// The costs measured on the host, in the cycles of the host clock.
+#[derive(Debug)]
+pub struct Measurements {
+    // The frequency of the host clock in cycles per second; see `host::source()`.
+    pub tsc_hz: f64,
+
+    // A write and a read on a pipe, without a switch.
+    pub pipe_cycles: f64,
+
+    // A switch between two processes on the same core.
+    pub context_switch_cycles: f64,
+
+    // A signal sent to the process itself, with the handler and the return from it.
+    pub signal_cycles: f64,
+
+    // A change of the rights of a protection key; None if the host doesn't support MPK.
+    pub pkey_set_cycles: Option<f64>,
+}
+
+// The number of signals handled so far.
+static SIGNALS: AtomicU64 = AtomicU64::new(0);
+
+extern "C" fn on_signal(_: c_int) {
+    SIGNALS.fetch_add(1, Ordering::Relaxed);
+}
+
+// Create a pipe; the read and the write end.
+fn pipe() -> Result<(c_int, c_int), String> {
+    let mut fds = [0 as c_int; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(format!(
+            "pipe() failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok((fds[0], fds[1]))
+}
+
+// A measurement averages over the iterations; none would give a NaN cost.
+fn check_iterations(iterations: u64) -> Result<(), String> {
+    if iterations == 0 {
+        return Err(String::from("The number of iterations must be positive"));
+    }
+    Ok(())
+}
+
+// Write one byte to the pipe, and read it back from the other one.
+fn ping(write: c_int, read: c_int, byte: u8) -> Result<(), String> {
+    let written = unsafe { libc::write(write, &byte as *const u8 as *const libc::c_void, 1) };
+    if written != 1 {
+        return Err(format!(
+            "write() on the pipe failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    let mut echo = !byte;
+    match unsafe { libc::read(read, &mut echo as *mut u8 as *mut libc::c_void, 1) } {
+        1 if echo == byte => Ok(()),
+        1 => Err(String::from(
+            "The byte read back from the pipe isn't the one written",
+        )),
+        0 => Err(String::from("The other end of the pipe was closed")),
+        _ => Err(format!(
+            "read() on the pipe failed: {}",
+            std::io::Error::last_os_error()
+        )),
+    }
+}
+
+/// This function measures a write and a read on a pipe within one process.
+///
+/// # Arguments
+/// `iterations`: The number of round trips to average over.
+pub fn pipe_overhead(iterations: u64) -> Result<f64, String> {
+    check_iterations(iterations)?;
+    let (read, write) = pipe()?;
+    let start = host::rdtsc();
+    let mut pinged = Ok(());
+    for i in 0..iterations {
+        pinged = ping(write, read, i as u8);
+        if pinged.is_err() {
+            break;
+        }
+    }
+    let cycles = host::rdtsc() - start;
+    unsafe {
+        libc::close(read);
+        libc::close(write);
+    }
+    pinged?;
+    Ok(cycles as f64 / iterations as f64)
+}
+
+/// This function measures the switch between two processes, like lmbench's lat_ctx. Two forked
+/// processes on the same core pass a byte back and forth over two pipes; each round trip is two
+/// switches and two pipe round trips.
+///
+/// # Arguments
+/// `iterations`: The number of round trips to average over.
+/// `pipe_cycles`: The cost of the pipes in a round trip, from `pipe_overhead()`.
+pub fn context_switch(iterations: u64, pipe_cycles: f64) -> Result<f64, String> {
+    check_iterations(iterations)?;
+    let (parent_read, child_write) = pipe()?;
+    let (child_read, parent_write) = pipe()?;
+
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        return Err(format!(
+            "fork() failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    // Each process keeps only its own ends of the pipes, so that either sees the end of the pipe
+    // if the other one exits.
+    if pid == 0 {
+        unsafe {
+            libc::close(parent_read);
+            libc::close(parent_write);
+        }
+        // The parent sends the number of the iteration; echo it back, or exit with an error if a
+        // byte is missing or out of order.
+        for i in 0..iterations {
+            let mut byte = !(i as u8);
+            unsafe {
+                if libc::read(child_read, &mut byte as *mut u8 as *mut libc::c_void, 1) != 1
+                    || byte != i as u8
+                    || libc::write(child_write, &byte as *const u8 as *const libc::c_void, 1) != 1
+                {
+                    libc::_exit(1);
+                }
+            }
+        }
+        unsafe { libc::_exit(0) };
+    }
+    unsafe {
+        libc::close(child_read);
+        libc::close(child_write);
+    }
+
+    let start = host::rdtsc();
+    let mut pinged = Ok(());
+    for i in 0..iterations {
+        pinged = ping(parent_write, parent_read, i as u8);
+        if pinged.is_err() {
+            break;
+        }
+    }
+    let cycles = host::rdtsc() - start;
+
+    // Closing the pipes ends the child too, if the round trips stopped early.
+    unsafe {
+        libc::close(parent_read);
+        libc::close(parent_write);
+    }
+    let mut status = 0;
+    let waited = unsafe { libc::waitpid(pid, &mut status, 0) };
+
+    // The round trips didn't measure what they should have if the child failed.
+    if waited != pid || !libc::WIFEXITED(status) || libc::WEXITSTATUS(status) != 0 {
+        return Err(format!(
+            "The forked process of the context-switch measurement failed; status {}",
+            status
+        ));
+    }
+    pinged?;
+
+    let round_trip = cycles as f64 / iterations as f64;
+    Ok(((round_trip - 2.0 * pipe_cycles) / 2.0).max(0.0))
+}
+
+/// This function measures the delivery of a signal; the process sends it to itself, and the
+/// handler runs before kill() returns.
+///
+/// # Arguments
+/// `iterations`: The number of signals to average over.
+pub fn signal(iterations: u64) -> Result<f64, String> {
+    check_iterations(iterations)?;
+    unsafe {
+        if libc::signal(libc::SIGUSR1, on_signal as *const () as libc::sighandler_t)
+            == libc::SIG_ERR
+        {
+            return Err(String::from("Failure installing the SIGUSR1 handler"));
+        }
+    }
+
+    let handled = SIGNALS.load(Ordering::Relaxed);
+    let pid = unsafe { libc::getpid() };
+    let start = host::rdtsc();
+    for _ in 0..iterations {
+        unsafe { libc::kill(pid, libc::SIGUSR1) };
+    }
+    let cycles = host::rdtsc() - start;
+
+    unsafe { libc::signal(libc::SIGUSR1, libc::SIG_DFL) };
+    if SIGNALS.load(Ordering::Relaxed) - handled != iterations {
+        return Err(String::from("Some of the signals weren't handled"));
+    }
+    Ok(cycles as f64 / iterations as f64)
+}
+
+/// This function measures a change of the rights of a protection key with pkey_set(), which
+/// writes the PKRU register.
+///
+/// # Arguments
+/// `iterations`: The number of changes to average over.
+///
+/// # Return
+/// None if the host can't allocate a protection key.
+pub fn pkey(iterations: u64) -> Result<Option<f64>, String> {
+    check_iterations(iterations)?;
+    let key = unsafe { libc::syscall(libc::SYS_pkey_alloc, 0, 0) } as c_int;
+    if key < 0 {
+        return Ok(None);
+    }
+
+    let start = host::rdtsc();
+    for i in 0..iterations {
+        unsafe { pkey_set(key, (i & 1) as c_uint) };
+    }
+    let cycles = host::rdtsc() - start;
+
+    unsafe { libc::syscall(libc::SYS_pkey_free, key) };
+    Ok(Some(cycles as f64 / iterations as f64))
+}
+
+/// This function runs all the measurements on one core.
+///
+/// # Arguments
+/// `iterations`: The number of repetitions of each measurement.
+/// `core`: The core to run on; the forked process runs on it too.
+pub fn measure(iterations: u64, core: usize) -> Result<Measurements, String> {
+    if !core_affinity::set_for_current(core_affinity::CoreId { id: core }) {
+        return Err(format!("Failure pinning to core {}", core));
+    }
+
+    let pipe_cycles = pipe_overhead(iterations)?;
+    Ok(Measurements {
+        tsc_hz: host::cycles_per_second() as f64,
+        pipe_cycles,
+        context_switch_cycles: context_switch(iterations, pipe_cycles)?,
+        signal_cycles: signal(iterations)?,
+        pkey_set_cycles: pkey(iterations)?,
+    })
+}
+
+impl Measurements {
+    /// This method converts the measurements to a cost profile, in the cycles of the virtual CPU.
+    /// The parts of the costs which aren't measured, like the swap of contexts in the preemption,
+    /// are kept from the default profile; the mechanisms which aren't measured are left out, so
+    /// the profile goes on top of the default one.
+    ///
+    /// # Arguments
+    /// `clock`: The virtual clock the costs are counted in.
+    pub fn profile(&self, clock: &Clock) -> Profile {
+        let defaults = CostModel::default().resolve().unwrap();
+        let scale = |host_cycles: f64| {
+            (host_cycles * clock.cycles_per_second() as f64 / self.tsc_hz).round() as u64
+        };
+
+        let signal_cycles = scale(self.signal_cycles);
+        let mut profile = Profile {
+            signal_cycles: Some(signal_cycles),
+            ..Default::default()
+        };
+
+        let mut mechanism = |isolation: Isolation, switch_cycles: u64| {
+            let preemption = defaults.preemption_cycles(&isolation, false);
+            let cost = MechanismCost {
+                switch_cycles,
+                preemption_cycles: preemption - defaults.signal_cycles + signal_cycles,
+                ipi: false,
+            };
+            profile
+                .mechanisms
+                .insert(isolation.name().to_string(), cost);
+        };
+
+        mechanism(
+            Isolation::NoIsolation,
+            defaults.switch_cycles(&Isolation::NoIsolation),
+        );
+        mechanism(
+            Isolation::PageTableIsolation,
+            scale(self.context_switch_cycles),
+        );
+
+        // A switch between MPK domains enters and leaves the trampoline, writing the PKRU twice.
+        if let Some(pkey_set_cycles) = self.pkey_set_cycles {
+            mechanism(
+                Isolation::MpkIsolation,
+                scale(2.0 * pkey_set_cycles) + consts::SWAP_CONTEXT_CYCLES,
+            );
+        }
+        profile
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn measurements(pkey_set_cycles: Option<f64>) -> Measurements {
+        Measurements {
+            tsc_hz: 2e9,
+            pipe_cycles: 1000.0,
+            context_switch_cycles: 2000.0,
+            signal_cycles: 1500.0,
+            pkey_set_cycles,
+        }
+    }
+
+    #[test]
+    fn test_profile() {
+        // The host runs at 2 GHz and the simulated CPU at 3 GHz; the costs scale by 1.5.
+        let clock = Clock::from_ghz(3.0);
+        let model = CostModel {
+            profiles: vec![String::from("Default")],
+            overrides: measurements(Some(20.0)).profile(&clock),
+        };
+        let costs = model.resolve().unwrap();
+        let defaults = CostModel::default().resolve().unwrap();
+        assert_eq!(costs.signal_cycles, 2250);
+        assert_eq!(costs.switch_cycles(&Isolation::PageTableIsolation), 3000);
+        assert_eq!(
+            costs.switch_cycles(&Isolation::MpkIsolation),
+            60 + consts::SWAP_CONTEXT_CYCLES
+        );
+        assert_eq!(
+            costs.preemption_cycles(&Isolation::PageTableIsolation, false),
+            defaults.preemption_cycles(&Isolation::PageTableIsolation, false)
+                - defaults.signal_cycles
+                + 2250
+        );
+        assert_eq!(
+            costs.switch_cycles(&Isolation::VmfuncIsolation),
+            defaults.switch_cycles(&Isolation::VmfuncIsolation)
+        );
+
+        // Without MPK on the host, the MPK costs stay the default ones.
+        let profile = measurements(None).profile(&clock);
+        assert!(!profile
+            .mechanisms
+            .contains_key(Isolation::MpkIsolation.name()));
+        let model = CostModel {
+            profiles: vec![String::from("Default")],
+            overrides: profile,
+        };
+        let costs = model.resolve().unwrap();
+        assert_eq!(
+            costs.mechanism(&Isolation::MpkIsolation).unwrap(),
+            defaults.mechanism(&Isolation::MpkIsolation).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_context_switch() {
+        // More than 256 round trips, so the bytes sent wrap around.
+        let pipe_cycles = pipe_overhead(1000).unwrap();
+        assert!(pipe_cycles > 0.0);
+        assert!(context_switch(1000, pipe_cycles).is_ok());
+    }
+
+    #[test]
+    fn test_zero_iterations() {
+        assert!(pipe_overhead(0).is_err());
+        assert!(context_switch(0, 0.0).is_err());
+        assert!(signal(0).is_err());
+        assert!(pkey(0).is_err());
+    }
+}
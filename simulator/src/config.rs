@@ -13,35 +13,445 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
+use super::consts;
+use super::costs::{CostModel, Costs};
+use super::cycles;
+use super::service::{ServiceTime, TenantServiceTime};
+
 use std::fs::File;
 use std::io::Read;
 
+use common::histogram;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use toml::Value;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum Distribution {
     Uniform,
     Zipf,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "String", into = "String")]
 pub enum Isolation {
     NoIsolation,
     PageTableIsolation,
     MpkIsolation,
     VmfuncIsolation,
+
+    // A mechanism defined in the cost model; each switch costs its own switch cycles.
+    Custom(String),
+}
+
+impl Isolation {
+    /// The name of the mechanism, as in the configuration and the cost model.
+    pub fn name(&self) -> &str {
+        match self {
+            Isolation::NoIsolation => "NoIsolation",
+            Isolation::PageTableIsolation => "PageTableIsolation",
+            Isolation::MpkIsolation => "MpkIsolation",
+            Isolation::VmfuncIsolation => "VmfuncIsolation",
+            Isolation::Custom(name) => name,
+        }
+    }
+}
+
+impl From<String> for Isolation {
+    fn from(name: String) -> Isolation {
+        match name.as_str() {
+            "NoIsolation" => Isolation::NoIsolation,
+            "PageTableIsolation" => Isolation::PageTableIsolation,
+            "MpkIsolation" => Isolation::MpkIsolation,
+            "VmfuncIsolation" => Isolation::VmfuncIsolation,
+            _ => Isolation::Custom(name),
+        }
+    }
+}
+
+impl From<Isolation> for String {
+    fn from(isolation: Isolation) -> String {
+        isolation.name().to_string()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Policy {
     RoundRobin,
     Minos,
+
+    // One dispatcher core hands the requests of all the tenants, from a global queue, to the
+    // idle worker cores and preempts them with IPIs.
+    Shinjuku,
+
+    // Shortest remaining processing time first; over all the tenants of a core.
+    Srpt,
+
+    // Earliest deadline first; the deadline of a request is its arrival time plus the SLO of
+    // its tenant.
+    Edf,
+
+    // Strict priority classes; round robin among the tenants of the highest class with requests.
+    Priority,
+
+    // Processor sharing; the runnable requests of a core take turns with a tiny quanta, and the
+    // switches and preemptions are free. A theoretical baseline.
+    ProcessorSharing,
+
+    // Deficit round robin; each round, a tenant may run for drr_quantum_us times its weight.
+    Drr,
+
+    // Weighted fair queuing; the request with the earliest virtual finish time runs first.
+    Wfq,
+
+    // Lottery scheduling; the next tenant is drawn at random, with odds in proportion to weight.
+    Lottery,
+
+    // Stride scheduling; the tenant which has run the least for its weight runs first.
+    Stride,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum Steal {
+    // The cores only run the requests of their own tenants.
+    #[default]
+    NoStealing,
+
+    // An idle core takes one runnable request from the most loaded core.
+    RequestStealing,
+
+    // An idle core takes all the runnable requests of one tenant from the most loaded core.
+    TenantStealing,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum KeyPolicy {
+    // The tenants of a core are split in fixed domains of `keys` tenants, each in its own
+    // address space; switching between domains is a full context-switch.
+    Static,
+
+    // All the tenants of a core share one address space and the keys are virtualized; a tenant
+    // without a key takes the least recently used one.
+    Lru,
+
+    // Like Lru, but takes the key used the least since it was assigned.
+    Lfu,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MpkConfig {
+    // How the tenants of a core share the protection keys; Static, Lru or Lfu.
+    pub policy: KeyPolicy,
+
+    // The number of protection keys for the tenants in one address space; at most 15.
+    pub keys: u32,
+
+    // The CPU cycles to take a key from one tenant and give it to another.
+    pub retag_cycles: u64,
+}
+
+impl Default for MpkConfig {
+    fn default() -> MpkConfig {
+        MpkConfig {
+            policy: KeyPolicy::Static,
+            keys: consts::MPK_KEYS,
+            retag_cycles: consts::MPK_RETAG_CYCLES,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct VmfuncConfig {
+    // How the tenants of a core share the EPTP list; Static (fixed domains of `eptp_entries`
+    // tenants), Lru or Lfu (the list is a cache over all the tenants of the core).
+    pub policy: KeyPolicy,
+
+    // The number of entries in the EPTP list; at most 512.
+    pub eptp_entries: u32,
+
+    // The CPU cycles to load the EPT of a tenant which isn't in the EPTP list.
+    pub reload_cycles: u64,
+
+    // If the TLB entries are tagged with the VPID and EPTP; otherwise each switch flushes the TLB.
+    pub tagged: bool,
+
+    // The CPU cycles to refill the TLB after a switch, when the entries aren't tagged.
+    pub flush_cycles: u64,
+}
+
+impl Default for VmfuncConfig {
+    fn default() -> VmfuncConfig {
+        VmfuncConfig {
+            policy: KeyPolicy::Static,
+            eptp_entries: consts::EPTP_LIST_ENTRIES,
+            reload_cycles: consts::EPTP_RELOAD_CYCLES,
+            tagged: true,
+            flush_cycles: consts::TLB_FLUSH_CYCLES,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CacheConfig {
+    // Model the cache and TLB misses after a switch; otherwise a switch costs a fixed number of
+    // cycles.
+    pub enabled: bool,
+
+    // The share of the last-level cache of one core in KB.
+    pub capacity_kb: f64,
+
+    // The memory touched by the requests of each tenant in KB.
+    pub working_set_kb: f64,
+
+    // The number of entries in the TLB; 4 KB pages.
+    pub tlb_entries: u32,
+
+    // The CPU cycles to bring one missing 64-byte line into the cache.
+    pub line_miss_cycles: u64,
+
+    // The CPU cycles to walk the page tables on a TLB miss.
+    pub tlb_miss_cycles: u64,
+
+    // If the TLB entries are tagged with a PCID; otherwise a page-table switch flushes the TLB.
+    pub pcid: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> CacheConfig {
+        CacheConfig {
+            enabled: false,
+            capacity_kb: consts::CACHE_CAPACITY_KB,
+            working_set_kb: consts::WORKING_SET_KB,
+            tlb_entries: consts::TLB_ENTRIES,
+            line_miss_cycles: consts::LINE_MISS_CYCLES,
+            tlb_miss_cycles: consts::TLB_MISS_CYCLES,
+            pcid: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum Placement {
+    // Each core runs a contiguous range of tenant-ids.
+    #[default]
+    Contiguous,
+
+    // The tenants are spread over the cores by a hash of their id.
+    Hashed,
+
+    // The tenants, heaviest first, go to the core with the least expected load.
+    LoadAware,
+
+    // Like LoadAware, but whole isolation domains of consecutive tenant-ids move together, so
+    // that the tenants which share a domain share a core too.
+    DomainAware,
+}
+
+/// The scheduling parameters of a range of tenants; the ones not given come from the defaults in
+/// the configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TenantClass {
+    // The first tenant-id in the range.
+    pub first: u64,
+
+    // The last tenant-id in the range; inclusive.
+    pub last: u64,
+
+    // The latency objective of the requests of these tenants in micro-seconds.
+    #[serde(default)]
+    pub slo_us: Option<f64>,
+
+    // The priority class of these tenants; zero is the highest.
+    #[serde(default)]
+    pub priority: Option<u32>,
+
+    // The share of the core of each of these tenants, relative to the other tenants of the core.
+    #[serde(default)]
+    pub weight: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RebalanceConfig {
+    // The time between two rebalancing rounds in micro-seconds; zero disables the rebalancer.
+    pub interval_us: f64,
+
+    // A round moves tenants while the most loaded core is more than this fraction above the
+    // mean load.
+    pub threshold: f64,
+
+    // The maximum number of tenants moved in one round.
+    pub max_migrations: u64,
+
+    // The CPU cycles the core which takes in a migrated tenant spends on it.
+    pub migration_cycles: u64,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> RebalanceConfig {
+        RebalanceConfig {
+            interval_us: 0.0,
+            threshold: 0.1,
+            max_migrations: 4,
+            migration_cycles: consts::MIGRATION_CYCLES,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TopologyConfig {
+    // The number of sockets of the server.
+    pub sockets: u64,
+
+    // The number of NUMA nodes of each socket.
+    pub numa_nodes: u64,
+
+    // The number of hardware threads of each physical core; consecutive cores are SMT siblings.
+    pub smt: u64,
+
+    // The CPU cycles a switch costs extra when the memory of the tenant is on another NUMA node
+    // of the same socket; the memory of a tenant is on the node of the core it is placed on first.
+    pub remote_numa_cycles: u64,
+
+    // The same when the memory of the tenant is on another socket.
+    pub remote_socket_cycles: u64,
+}
+
+impl Default for TopologyConfig {
+    fn default() -> TopologyConfig {
+        TopologyConfig {
+            sockets: 1,
+            numa_nodes: 1,
+            smt: 1,
+            remote_numa_cycles: consts::REMOTE_NUMA_CYCLES,
+            remote_socket_cycles: consts::REMOTE_SOCKET_CYCLES,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MinosConfig {
+    // The time between two decisions on the number of large cores in micro-seconds; zero keeps
+    // the initial split.
+    pub interval_us: f64,
+}
+
+impl Default for MinosConfig {
+    fn default() -> MinosConfig {
+        MinosConfig {
+            interval_us: consts::MINOS_INTERVAL_US,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum Arrival {
+    #[default]
+    Deterministic,
+    Poisson,
+    OnOff,
+    Diurnal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct OnOffConfig {
+    // The arrival rate during the on-periods, as a multiple of `req_rate`.
+    pub on_factor: f64,
+
+    // The mean length of the on-periods in micro-seconds.
+    pub on_time_us: f64,
+
+    // The mean length of the off-periods in micro-seconds.
+    pub off_time_us: f64,
+}
+
+impl Default for OnOffConfig {
+    fn default() -> OnOffConfig {
+        OnOffConfig {
+            on_factor: 2.0,
+            on_time_us: 100.0,
+            off_time_us: 100.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DiurnalConfig {
+    // The length of one period of the rate curve in micro-seconds.
+    pub period_us: f64,
+
+    // The amplitude of the rate curve, relative to `req_rate`; between 0 and 1.
+    pub amplitude: f64,
+}
+
+impl Default for DiurnalConfig {
+    fn default() -> DiurnalConfig {
+        DiurnalConfig {
+            period_us: 1000000.0,
+            amplitude: 0.5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum TraceFormat {
+    Csv,
+    Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum TenantMap {
+    // Tenant `id` becomes tenant `id % num_tenants + 1`.
+    Modulo,
+
+    // Tenants are numbered in the order of their first request, modulo `num_tenants`.
+    Dense,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TraceConfig {
+    // The trace to replay instead of generating requests; empty to generate the requests.
+    pub path: String,
+
+    // The format of the trace file; Csv or Binary.
+    pub format: TraceFormat,
+
+    // The arrival times are multiplied by this factor; e.g. 0.5 replays at twice the rate.
+    pub time_scale: f64,
+
+    // This is to decide how the tenant-ids in the trace map onto `num_tenants`.
+    pub tenant_map: TenantMap,
+}
+
+impl Default for TraceConfig {
+    fn default() -> TraceConfig {
+        TraceConfig {
+            path: String::new(),
+            format: TraceFormat::Csv,
+            time_scale: 1.0,
+            tenant_map: TenantMap::Modulo,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
-    // The maximum number of small cores used for the simultaion.
-    pub small_cores: u64,
+    // The number of cores used for the simulation, the large ones included. It used to be
+    // `small_cores`, which didn't count the large cores.
+    #[serde(alias = "small_cores")]
+    pub num_cores: u64,
 
-    // The maximum number of large cores for the simulation.
+    // The number of the cores, out of num_cores, which start as large cores with the Minos policy;
+    // zero to size them from the expected load of the long requests. Minos adapts the split at
+    // runtime.
     pub large_cores: u64,
 
     // The number of teants the client will generate requests for.
@@ -62,28 +472,600 @@ pub struct Config {
     // Execute all the tasks for a tenant for each iteration.
     pub batching: bool,
 
+    // Preempt the requests at the end of the time quanta; otherwise each one runs to completion.
+    #[serde(default = "preemption")]
+    pub preemption: bool,
+
     // This is to decide which memory isolation to use; No isolation, Paging, MPK, VMFunc.
     pub isolation: Isolation,
 
     // This is to decide which tenant distribution to use; Uniform Random or Zipfian.
     pub distribution: Distribution,
 
-    // This is to decide the scheduling policy; Round Robin or minos like core partitioning.
+    // This is to decide the scheduling policy; Round Robin, minos like core partitioning or a
+    // Shinjuku like central dispatcher.
     pub policy: Policy,
+
+    // The seed for the random number generators; a random seed is picked if not given.
+    #[serde(default = "random_seed")]
+    pub seed: u64,
+
+    // This is to decide when the requests arrive; Deterministic, Poisson, OnOff or Diurnal.
+    #[serde(default)]
+    pub arrival: Arrival,
+
+    // The parameters for the OnOff arrival process.
+    #[serde(default)]
+    pub on_off: OnOffConfig,
+
+    // The parameters for the Diurnal arrival process.
+    #[serde(default)]
+    pub diurnal: DiurnalConfig,
+
+    // The trace to replay; the requests come from the trace instead of the dispatchers.
+    #[serde(default)]
+    pub trace: TraceConfig,
+
+    // Requests longer than this, in micro-seconds, are long requests; Minos runs them on the
+    // large cores and the results report their latencies separately.
+    #[serde(default = "minos_threshold")]
+    pub minos_threshold_us: f64,
+
+    // The adaptive split of the cores between the short and the long requests with Minos.
+    #[serde(default)]
+    pub minos: MinosConfig,
+
+    // The sockets, NUMA nodes and SMT siblings of the cores.
+    #[serde(default)]
+    pub topology: TopologyConfig,
+
+    // The latency objective of the requests in micro-seconds, for the tenants without their own.
+    #[serde(default = "slo_us")]
+    pub slo_us: f64,
+
+    // The time quanta of the ProcessorSharing policy in micro-seconds.
+    #[serde(default = "ps_quanta")]
+    pub ps_quanta_us: f64,
+
+    // The service, in micro-seconds, a tenant of weight one gets in each round of the Drr policy.
+    #[serde(default = "drr_quantum")]
+    pub drr_quantum_us: f64,
+
+    // The number of completed requests, over all the cores, to discard before measuring.
+    #[serde(default)]
+    pub warmup_requests: u64,
+
+    // The simulated time, in micro-seconds, to discard before measuring.
+    #[serde(default)]
+    pub warmup_time_us: f64,
+
+    // The length of the measurement window in micro-seconds; zero to measure until the end.
+    #[serde(default)]
+    pub measure_duration_us: f64,
+
+    // The number of significant bits kept for each latency; between 1 and 16. The latencies are
+    // recorded within a relative error of 2^-histogram_precision.
+    #[serde(default = "histogram_precision")]
+    pub histogram_precision: u32,
+
+    // The same for the latencies of each tenant; each tenant keeps its own histograms, so a lower
+    // precision saves memory with many tenants.
+    #[serde(default = "histogram_precision")]
+    pub tenant_histogram_precision: u32,
+
+    // The frequency of the simulated CPU in GHz; the simulated time and the costs are in its
+    // cycles, whatever the frequency of the host.
+    #[serde(default = "frequency_ghz")]
+    pub frequency_ghz: f64,
+
+    // This is to decide if idle cores steal work from the other cores; NoStealing, RequestStealing
+    // or TenantStealing.
+    #[serde(default)]
+    pub steal: Steal,
+
+    // The cost of one steal in CPU cycles, on top of the switch to the stolen tenant.
+    #[serde(default = "steal_cycles")]
+    pub steal_cycles: u64,
+
+    // The CPU cycles the central dispatcher spends on each request it hands to a worker.
+    #[serde(default = "dispatch_cycles")]
+    pub dispatch_cycles: u64,
+
+    // This is to decide which core runs each tenant; Contiguous, Hashed, LoadAware or
+    // DomainAware. The requests still arrive at the core which generates them.
+    #[serde(default)]
+    pub placement: Placement,
+
+    // The periodic rebalancer, which migrates tenants from the most to the least loaded core.
+    #[serde(default)]
+    pub rebalance: RebalanceConfig,
+
+    // The model of the MPK protection keys.
+    #[serde(default)]
+    pub mpk: MpkConfig,
+
+    // The model of the VMFUNC EPTP list.
+    #[serde(default)]
+    pub vmfunc: VmfuncConfig,
+
+    // The model of the cache and TLB misses after a tenant switch.
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    // The profiles and the overrides for the switch and preemption costs.
+    #[serde(default)]
+    pub cost_model: CostModel,
+
+    // The costs from `cost_model`; filled in by `load_with()`, and the default profile otherwise.
+    #[serde(skip)]
+    pub costs: Costs,
+
+    // The service time distribution of the requests.
+    #[serde(default)]
+    pub service_time: ServiceTime,
+
+    // The service time distributions for ranges of tenants, overriding `service_time`.
+    #[serde(default)]
+    pub tenant_service_time: Vec<TenantServiceTime>,
+
+    // The SLOs and the priority classes of ranges of tenants.
+    #[serde(default)]
+    pub tenant_class: Vec<TenantClass>,
 }
 
 impl Config {
     pub fn load() -> Config {
+        match Config::load_with("config.toml", &[]) {
+            Ok(config) => config,
+            Err(e) => {
+                panic!("{}", e);
+            }
+        }
+    }
+
+    /// This method reads the configuration file and applies the overrides on top of it.
+    ///
+    /// # Arguments
+    /// `filename`: The path of the TOML configuration file.
+    /// `overrides`: The `key=value` pairs to override; nested keys are separated by dots.
+    ///
+    /// # Return
+    /// The configuration, or a message describing why it couldn't be loaded.
+    pub fn load_with(filename: &str, overrides: &[String]) -> Result<Config, String> {
         let mut contents = String::new();
-        let filename = "config.toml";
+        File::open(filename)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("Failure reading config file {}: {}", filename, e))?;
 
-        let _ = File::open(filename).and_then(|mut file| file.read_to_string(&mut contents));
+        let mut table: Value = toml::from_str(&contents)
+            .map_err(|e| format!("Failure parsing config file {}: {}", filename, e))?;
+        for o in overrides {
+            let (key, value) = split_override(o)?;
 
-        match toml::from_str(&contents) {
-            Ok(config) => config,
-            Err(e) => {
-                panic!("Failure paring config file {}: {}", filename, e);
+            // num_cores and its old name set the same field; the override replaces both.
+            if key == "num_cores" || key == "small_cores" {
+                if let Some(table) = table.as_table_mut() {
+                    table.remove("num_cores");
+                    table.remove("small_cores");
+                }
+            }
+            set_value(&mut table, key, value)?;
+        }
+
+        // small_cores used to leave out the large cores; the old Minos configurations would
+        // silently lose them.
+        let small_cores = get_value(&table, "small_cores").and_then(|v| v.as_integer());
+        let large_cores = get_value(&table, "large_cores").and_then(|v| v.as_integer());
+        if let (Some(small), Some(large)) = (small_cores, large_cores) {
+            if large != 0 {
+                return Err(format!(
+                    "small_cores is now num_cores, which counts the large cores too; set \
+                     num_cores = {} instead",
+                    small + large
+                ));
+            }
+        }
+
+        let mut config: Config = table
+            .try_into()
+            .map_err(|e| format!("Invalid configuration: {}", e))?;
+        config.costs = config.cost_model.resolve()?;
+        config.costs.mechanism(&config.isolation)?;
+        config.costs.mechanism(&Isolation::PageTableIsolation)?;
+        config.validate()?;
+
+        // Reject the overrides which don't name any field of the configuration.
+        let known = config.to_value()?;
+        for o in overrides {
+            let (mut key, _) = split_override(o)?;
+            if key == "small_cores" {
+                key = "num_cores";
+            }
+            if get_value(&known, key).is_none() {
+                return Err(format!("Unknown configuration key '{}'", key));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// This method checks the values which are valid TOML but make no sense for the simulator.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.num_cores == 0 || self.num_tenants < self.num_cores {
+            return Err("each core needs at least one tenant".to_string());
+        }
+        if self.num_tenants >= u32::MAX as u64 {
+            return Err(format!("num_tenants can't be more than {}", u32::MAX - 1));
+        }
+
+        let topology = &self.topology;
+        if topology.sockets == 0 || topology.numa_nodes == 0 || topology.smt == 0 {
+            return Err("topology needs at least one socket, NUMA node and thread".to_string());
+        }
+        if !self.num_cores.is_multiple_of(topology.smt)
+            || self.num_cores / topology.smt < topology.sockets * topology.numa_nodes
+        {
+            return Err(
+                "the cores must be whole physical cores, with one on each NUMA node".to_string(),
+            );
+        }
+
+        if self.req_rate == 0 {
+            return Err("req_rate must be positive".to_string());
+        }
+
+        let on_off = &self.on_off;
+        if on_off.on_time_us <= 0.0 || on_off.off_time_us <= 0.0 || on_off.on_factor < 1.0 {
+            return Err("on_off needs positive periods and on_factor >= 1".to_string());
+        }
+        if on_off.on_factor * on_off.on_time_us > on_off.on_time_us + on_off.off_time_us {
+            return Err(format!(
+                "on_off.on_factor {} is too large; the mean rate can't stay req_rate",
+                on_off.on_factor
+            ));
+        }
+
+        if self.diurnal.period_us <= 0.0 || !(0.0..=1.0).contains(&self.diurnal.amplitude) {
+            return Err("diurnal needs a positive period and an amplitude in [0, 1]".to_string());
+        }
+
+        if !self.trace.path.is_empty() && self.trace.time_scale <= 0.0 {
+            return Err("trace.time_scale must be positive".to_string());
+        }
+
+        if self.warmup_time_us < 0.0 || self.measure_duration_us < 0.0 {
+            return Err("warmup_time_us and measure_duration_us can't be negative".to_string());
+        }
+
+        // The virtual clock counts whole cycles; it needs at least one in each micro-second.
+        if !self.frequency_ghz.is_finite() || self.frequency_ghz < 0.001 {
+            return Err(
+                "frequency_ghz must be at least 0.001, one cycle per micro-second".to_string(),
+            );
+        }
+
+        if !(1..=16).contains(&self.histogram_precision)
+            || !(1..=16).contains(&self.tenant_histogram_precision)
+        {
+            return Err("histogram_precision must be between 1 and 16".to_string());
+        }
+
+        self.service_time.validate()?;
+        for t in &self.tenant_service_time {
+            if t.first > t.last {
+                return Err(format!(
+                    "tenant_service_time range {}..={} is empty",
+                    t.first, t.last
+                ));
             }
+            t.service_time.validate()?;
+        }
+
+        if self.slo_us <= 0.0 || self.ps_quanta_us <= 0.0 || self.drr_quantum_us <= 0.0 {
+            return Err("slo_us, ps_quanta_us and drr_quantum_us must be positive".to_string());
+        }
+        for t in &self.tenant_class {
+            if t.first > t.last {
+                return Err(format!(
+                    "tenant_class range {}..={} is empty",
+                    t.first, t.last
+                ));
+            }
+            if t.slo_us.is_some_and(|slo| slo <= 0.0) {
+                return Err(format!(
+                    "tenant_class {}..={} needs a positive slo_us",
+                    t.first, t.last
+                ));
+            }
+            if t.weight.is_some_and(|weight| weight <= 0.0) {
+                return Err(format!(
+                    "tenant_class {}..={} needs a positive weight",
+                    t.first, t.last
+                ));
+            }
+        }
+
+        let costs = &self.costs;
+        if costs.quanta_us <= 0.0 || costs.large_quanta_us <= 0.0 || costs.batch_size == 0 {
+            return Err(
+                "the cost model needs positive quanta and a batch_size of at least 1".to_string(),
+            );
+        }
+
+        if !self.preemption && self.policy == Policy::ProcessorSharing {
+            return Err("ProcessorSharing needs preemption".to_string());
+        }
+
+        if self.large_cores != 0 && self.policy != Policy::Minos {
+            return Err("only the Minos policy runs on large cores".to_string());
+        }
+
+        // Minos moves the requests between its small and large cores itself.
+        if self.policy == Policy::Minos {
+            if self.num_cores < 2 || self.large_cores >= self.num_cores {
+                return Err("Minos needs two cores, and fewer large cores than cores".to_string());
+            }
+            if self.steal != Steal::NoStealing {
+                return Err("Minos moves the requests between its cores; disable steal".to_string());
+            }
+            if self.minos.interval_us < 0.0 {
+                return Err("minos.interval_us can't be negative".to_string());
+            }
+        }
+
+        if !(1..=consts::MPK_KEYS).contains(&self.mpk.keys) {
+            return Err(format!(
+                "mpk.keys must be between 1 and {}",
+                consts::MPK_KEYS
+            ));
+        }
+
+        if !(1..=consts::EPTP_LIST_ENTRIES).contains(&self.vmfunc.eptp_entries) {
+            return Err(format!(
+                "vmfunc.eptp_entries must be between 1 and {}",
+                consts::EPTP_LIST_ENTRIES
+            ));
+        }
+
+        if self.cache.enabled && (self.cache.capacity_kb <= 0.0 || self.cache.tlb_entries == 0) {
+            return Err(String::from("The cache and the TLB can't be empty"));
+        }
+
+        if self.cache.working_set_kb < 0.0 {
+            return Err(String::from("cache.working_set_kb can't be negative"));
+        }
+
+        if self.rebalance.interval_us < 0.0 || self.rebalance.threshold < 0.0 {
+            return Err("rebalance needs a non-negative interval and threshold".to_string());
+        }
+        if (self.placement != Placement::Contiguous || self.rebalance.interval_us > 0.0)
+            && self.policy == Policy::Shinjuku
+        {
+            return Err(
+                "placement and rebalance need cores which run their own tenants".to_string(),
+            );
+        }
+
+        if self.policy == Policy::Shinjuku && self.steal != Steal::NoStealing {
+            return Err("the Shinjuku policy has a global queue; disable steal".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// The latency objective of the requests of a tenant in micro-seconds; from the first range
+    /// of `tenant_class` which sets it, or `slo_us`.
+    pub fn tenant_slo_us(&self, tenant: u32) -> f64 {
+        self.classes(tenant)
+            .find_map(|t| t.slo_us)
+            .unwrap_or(self.slo_us)
+    }
+
+    /// The priority class of a tenant; from the first range of `tenant_class` which sets it, or
+    /// zero, the highest.
+    pub fn tenant_priority(&self, tenant: u32) -> u32 {
+        self.classes(tenant).find_map(|t| t.priority).unwrap_or(0)
+    }
+
+    /// The share of the core of a tenant, relative to the other tenants of the core; from the
+    /// first range of `tenant_class` which sets it, or one.
+    pub fn tenant_weight(&self, tenant: u32) -> f64 {
+        self.classes(tenant).find_map(|t| t.weight).unwrap_or(1.0)
+    }
+
+    // The ranges of `tenant_class` which include the tenant.
+    fn classes(&self, tenant: u32) -> impl Iterator<Item = &TenantClass> {
+        let tenant = tenant as u64;
+        self.tenant_class
+            .iter()
+            .filter(move |t| tenant >= t.first && tenant <= t.last)
+    }
+
+    /// This method creates a random number generator derived from the seed, so that the same
+    /// configuration and seed always generate the same sequence of numbers.
+    ///
+    /// # Arguments
+    /// `core_id`: The core which uses the generator.
+    /// `stream`: Distinguishes the generators used for different purposes on the same core.
+    pub fn rng(&self, core_id: u32, stream: u64) -> StdRng {
+        let id = ((core_id as u64) << 32) | stream;
+        StdRng::seed_from_u64(self.seed ^ id.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    /// This method returns the virtual clock of the simulator, at `frequency_ghz`.
+    pub fn clock(&self) -> cycles::Clock {
+        cycles::Clock::from_ghz(self.frequency_ghz)
+    }
+
+    /// This method converts the configuration to a TOML value, e.g. to save it with the results.
+    pub fn to_value(&self) -> Result<Value, String> {
+        Value::try_from(self).map_err(|e| format!("Failure serializing config: {}", e))
+    }
+}
+
+// TOML integers are signed; keep the seed in range so that a saved configuration loads again.
+fn random_seed() -> u64 {
+    rand::random::<u64>() >> 1
+}
+
+fn preemption() -> bool {
+    true
+}
+
+fn minos_threshold() -> f64 {
+    consts::QUANTA_TIME
+}
+
+fn slo_us() -> f64 {
+    consts::SLO_US
+}
+
+fn ps_quanta() -> f64 {
+    consts::PS_QUANTA_TIME
+}
+
+fn drr_quantum() -> f64 {
+    consts::QUANTA_TIME
+}
+
+fn steal_cycles() -> u64 {
+    consts::STEAL_CYCLES
+}
+
+fn dispatch_cycles() -> u64 {
+    consts::DISPATCH_CYCLES
+}
+
+fn frequency_ghz() -> f64 {
+    cycles::DEFAULT_CYCLES_PER_SECOND as f64 / 1e9
+}
+
+fn histogram_precision() -> u32 {
+    histogram::DEFAULT_PRECISION
+}
+
+// Split a `key=value` override in its key and value.
+fn split_override(o: &str) -> Result<(&str, &str), String> {
+    let mut parts = o.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(key), Some(value)) if !key.trim().is_empty() => Ok((key.trim(), value.trim())),
+        _ => Err(format!("Invalid override '{}'; expected key=value", o)),
+    }
+}
+
+// Parse the value as TOML; bare words like `MpkIsolation` are taken as strings.
+fn parse_value(value: &str) -> Value {
+    match toml::from_str::<Value>(&format!("v = {}", value)) {
+        Ok(Value::Table(mut t)) => t.remove("v").unwrap_or(Value::String(value.to_string())),
+        _ => Value::String(value.to_string()),
+    }
+}
+
+fn set_value(table: &mut Value, key: &str, value: &str) -> Result<(), String> {
+    let mut curr = table;
+    let parts: Vec<&str> = key.split('.').collect();
+    for part in &parts[..parts.len() - 1] {
+        curr = match curr {
+            Value::Table(t) => t
+                .entry(part.to_string())
+                .or_insert_with(|| Value::Table(Default::default())),
+            _ => return Err(format!("Configuration key '{}' is not a table", part)),
+        };
+    }
+
+    match curr {
+        Value::Table(t) => {
+            t.insert(parts[parts.len() - 1].to_string(), parse_value(value));
+            Ok(())
+        }
+        _ => Err(format!("Invalid configuration key '{}'", key)),
+    }
+}
+
+fn get_value<'a>(table: &'a Value, key: &str) -> Option<&'a Value> {
+    let mut curr = table;
+    for part in key.split('.') {
+        curr = curr.get(part)?;
+    }
+    Some(curr)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_value() {
+        let mut table: Value = toml::from_str("num_tenants = 32\n[a]\nb = 1.5").unwrap();
+        set_value(&mut table, "num_tenants", "64").unwrap();
+        set_value(&mut table, "isolation", "MpkIsolation").unwrap();
+        set_value(&mut table, "a.b", "2.5").unwrap();
+        set_value(&mut table, "c.d", "true").unwrap();
+
+        assert_eq!(get_value(&table, "num_tenants"), Some(&Value::Integer(64)));
+        assert_eq!(
+            get_value(&table, "isolation"),
+            Some(&Value::String("MpkIsolation".to_string()))
+        );
+        assert_eq!(get_value(&table, "a.b"), Some(&Value::Float(2.5)));
+        assert_eq!(get_value(&table, "c.d"), Some(&Value::Boolean(true)));
+        assert!(split_override("num_tenants").is_err());
+    }
+
+    #[test]
+    fn test_small_cores() {
+        // The old name still works without large cores, and is rejected with them.
+        let small = vec!["small_cores=16".to_string()];
+        assert_eq!(
+            Config::load_with("config.toml", &small).unwrap().num_cores,
+            16
+        );
+
+        let minos: Vec<String> = ["small_cores=29", "large_cores=3", "policy=\"Minos\""]
+            .iter()
+            .map(|o| o.to_string())
+            .collect();
+        let e = Config::load_with("config.toml", &minos).unwrap_err();
+        assert!(e.contains("num_cores = 32"), "{}", e);
+    }
+
+    #[test]
+    fn test_validate_req_rate() {
+        for arrival in &["Deterministic", "Poisson", "OnOff", "Diurnal"] {
+            let overrides = vec![format!("arrival=\"{}\"", arrival), "req_rate=0".to_string()];
+            let e = Config::load_with("config.toml", &overrides).unwrap_err();
+            assert!(e.contains("req_rate"), "{}", e);
+        }
+    }
+
+    #[test]
+    fn test_validate_frequency() {
+        for f in &["0.0", "1e-10", "nan", "inf"] {
+            let overrides = vec![format!("frequency_ghz={}", f)];
+            let e = Config::load_with("config.toml", &overrides).unwrap_err();
+            assert!(e.contains("frequency_ghz"), "{}", e);
+        }
+        let overrides = vec!["frequency_ghz=0.001".to_string()];
+        let config = Config::load_with("config.toml", &overrides).unwrap();
+        assert_eq!(config.clock().cycles_per_second(), 1000000);
+    }
+
+    #[test]
+    fn test_validate_costs() {
+        let quanta = vec!["cost_model.quanta_us=5.0".to_string()];
+        assert_eq!(
+            Config::load_with("config.toml", &quanta)
+                .unwrap()
+                .costs
+                .quanta_us,
+            5.0
+        );
+        for o in &[
+            "cost_model.quanta_us=0.0",
+            "cost_model.large_quanta_us=0.0",
+            "cost_model.batch_size=0",
+        ] {
+            assert!(Config::load_with("config.toml", &[o.to_string()]).is_err());
         }
     }
 }
@@ -26,6 +26,9 @@ pub const MPK_TENANT_SWITCH_CYCLES: u64 = 950;
 // In CPU Cycles; taken from Shinjuku paper.(sysenter-sysexit + VMFunc + No Mask Switch). Also add 700 for swap context.
 pub const VMFUNC_TENANT_SWITCH_CYCLES: u64 = 1150;
 
+// In CPU cycles; the user-level swap of contexts included in the switch and preemption costs.
+pub const SWAP_CONTEXT_CYCLES: u64 = 700;
+
 //====================================================================================================================//
 // In CPU cycles. Shinjuku: 4900 to send-recieve signal(table 1) and 700 to swap context.
 pub const NOISOLATION_PREEMPTION_OVERHEAD_CYCLES: u64 = 5600;
@@ -41,6 +44,65 @@ pub const MPK_PREEMPTION_OVERHEAD_CYCLES: u64 = 5850;
 // VMFUNC_TENANT_SWITCH_CYCLES and 700 to swap context.
 pub const VMFUNC_PREEMPTION_OVERHEAD_CYCLES: u64 = 3350;
 
+// In CPU cycles. Shinjuku: 4900 to send-recieve a signal and 2000 to send-recieve a posted IPI(table 1);
+// the central dispatcher preempts the workers with an IPI instead of a signal.
+pub const SIGNAL_CYCLES: u64 = 4900;
+pub const IPI_CYCLES: u64 = 2000;
+
+//====================================================================================================================//
+// In CPU cycles; the cache misses to take a request from the run-queue of another core. The switch
+// to the stolen tenant is charged separately.
+pub const STEAL_CYCLES: u64 = 600;
+
+// In CPU cycles; the central dispatcher spends this much on each request it hands to a worker, which
+// caps it at 5M requests per second.
+pub const DISPATCH_CYCLES: u64 = 600;
+
+// In CPU cycles; assumed. The core which takes in a migrated tenant maps its memory and warms up its caches.
+pub const MIGRATION_CYCLES: u64 = 30000;
+
+//====================================================================================================================//
+// The number of protection keys for the tenants in one address space; 16 in hardware, minus the default key 0.
+pub const MPK_KEYS: u32 = 15;
+
+// In CPU cycles; assumed. libmpk-style key virtualization re-tags the pages of the evicted and of the new tenant
+// with pkey_mprotect(), and flushes the TLB.
+pub const MPK_RETAG_CYCLES: u64 = 12000;
+
+// The number of entries in the EPTP list of a core; one per tenant address space, fixed by the hardware.
+pub const EPTP_LIST_ENTRIES: u32 = 512;
+
+// In CPU cycles; assumed. A VM exit for the hypervisor to replace one entry of the EPTP list and invalidate the
+// mappings of the evicted EPT with INVEPT.
+pub const EPTP_RELOAD_CYCLES: u64 = 4000;
+
+// In CPU cycles; assumed. The TLB misses after a VMFUNC switch which flushed the TLB, without VPID/EPTP tagging.
+pub const TLB_FLUSH_CYCLES: u64 = 1500;
+
+//====================================================================================================================//
+// In KB; assumed. The share of the last-level cache of one core, e.g. 2.5 MB per core on a Xeon.
+pub const CACHE_CAPACITY_KB: f64 = 2560.0;
+
+// In KB; assumed. The memory touched by the requests of a tenant.
+pub const WORKING_SET_KB: f64 = 64.0;
+
+// The number of entries in the second-level TLB, e.g. on Skylake.
+pub const TLB_ENTRIES: u32 = 1536;
+
+// In CPU cycles; assumed. The cost of a missing 64-byte line, with the misses overlapped by the out-of-order core
+// and the prefetchers.
+pub const LINE_MISS_CYCLES: u64 = 20;
+
+// In CPU cycles; assumed. A page walk which mostly hits in the cache.
+pub const TLB_MISS_CYCLES: u64 = 30;
+
+// In CPU cycles; assumed. The extra cost of a switch to a tenant whose memory is on another NUMA
+// node of the same socket; its first accesses go over the interconnect.
+pub const REMOTE_NUMA_CYCLES: u64 = 1000;
+
+// In CPU cycles; assumed. The same for a tenant whose memory is on another socket.
+pub const REMOTE_SOCKET_CYCLES: u64 = 2500;
+
 //====================================================================================================================//
 //Batch-size for each tenant
 pub const BATCH_SIZE: usize = 8;
@@ -51,6 +113,12 @@ pub const QUANTA_TIME: f64 = 5.0;
 // Scheduler time qunata on large core in micro-seconds.
 pub const LARGE_QUNATA_TIME: f64 = 50.0;
 
-// Time distribution for short-running and long-running tasks.
-// Short-running tasks take 1 us and long running tasks take 1 ms.
-pub const TASK_DISTRIBUTION_TIME: [f64; 2] = [1.0, 1.0];
+// The time between two decisions on the number of large cores with Minos in micro-seconds.
+pub const MINOS_INTERVAL_US: f64 = 1000.0;
+
+// The time quanta of the processor sharing baseline in micro-seconds; small enough for the
+// requests to share the core evenly.
+pub const PS_QUANTA_TIME: f64 = 0.1;
+
+// The default latency objective of the requests in micro-seconds; EDF uses it for the deadlines.
+pub const SLO_US: f64 = 50.0;
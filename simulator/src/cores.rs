@@ -13,25 +13,42 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
-use super::config::{Config, Distribution as Dist, Isolation, Policy};
-use super::consts;
-use super::cycles;
+use super::cache::CacheModel;
+use super::config::{Config, Distribution as Dist, Isolation, KeyPolicy, Placement, Policy, Steal};
+use super::cycles::Clock;
 use super::dispatcher::Dispatch;
+use super::drr_sched::Drr;
+use super::edf_sched::Edf;
+use super::event::{Event, EventQueue, EventType};
+use super::lottery_sched::Lottery;
 use super::minos_sched::Minos;
+use super::mpk::KeyCache;
+use super::placement;
+use super::priority_sched::Priority;
+use super::ps_sched::ProcessorSharing;
+use super::report::{CoreReport, DispatcherReport, Report, Sample, TenantStats};
 use super::request::{Request, TaskState};
 use super::rr_sched::RoundRobin;
+use super::sched::{Context, Scheduler};
+use super::service::ServiceModel;
+use super::shinjuku::Shinjuku;
+use super::srpt_sched::Srpt;
+use super::stride_sched::Stride;
 use super::tenant::Tenant;
+use super::topology::Topology;
+use super::trace::{self, TraceRecord};
+use super::wfq_sched::Wfq;
+
+use common::histogram::Histogram;
 
 use std::cell::RefCell;
-use std::cmp::min;
-use std::collections::HashMap;
-use std::ops::Range;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::Arc;
 
-use rand::distributions::weighted::alias_method::WeightedIndex;
-use rand::distributions::Distribution;
-use rand::prelude::*;
-use rand::rngs::ThreadRng;
+use rand::rngs::StdRng;
 
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum CoreType {
@@ -42,134 +59,562 @@ pub enum CoreType {
 pub struct Simulator {
     config: Config,
     cores: Vec<Core>,
-    latencies: Vec<u64>,
-    tenants: HashMap<u64, Arc<RefCell<Tenant>>>,
+    latencies: Histogram,
+    tenants: HashMap<u64, Rc<RefCell<Tenant>>>,
+
+    // The latencies of the short and of the long requests; see `minos_threshold_us`.
+    classes: [Histogram; 2],
+
+    // The completed requests of each tenant, indexed by tenant-id - 1.
+    tenant_stats: Vec<TenantStats>,
+
+    // The number of requests completed so far, including the ones during the warm-up.
+    completed: u64,
+
+    // The time-stamp at which the measurement window started; None during the warm-up.
+    window_start: Option<u64>,
+
+    // The counters of each core at the start of the measurement window.
+    baseline: Vec<CoreReport>,
+
+    // The central dispatcher; only with the Shinjuku policy.
+    dispatcher: Option<Shinjuku>,
+
+    // The counters of the dispatcher at the start of the measurement window.
+    dispatcher_baseline: Option<DispatcherReport>,
+
+    // The small core which runs each tenant, indexed by tenant-id - 1.
+    owner: Vec<usize>,
+
+    // The sockets, NUMA nodes and SMT siblings of the cores; shared with the cores.
+    topology: Arc<Topology>,
+
+    // The global simulated time-stamp, shared by all the cores.
+    now: u64,
+
+    // The pending events for all the cores, ordered by their time-stamp.
+    events: EventQueue,
+
+    // The directory to write the results to, in addition to stdout.
+    out_dir: Option<PathBuf>,
+
+    // Don't print the results when the simulator is dropped.
+    quiet: bool,
+
+    // The requests to replay, if the configuration has a trace.
+    trace: Vec<TraceRecord>,
+
+    // The virtual clock, at the frequency of the configuration.
+    clock: Clock,
 }
 
 impl Simulator {
-    pub fn new() -> Simulator {
-        let config = Config::load();
-        info!("Starting the Simulator with config {:?}\n", config);
+    pub fn new(config: Config) -> Result<Simulator, String> {
+        let clock = config.clock();
+        let mut trace = Vec::new();
+        if !config.trace.path.is_empty() {
+            trace = trace::load(&config.trace, config.num_tenants, &clock)?;
+            info!(
+                "Replaying {} requests from {}",
+                trace.len(),
+                config.trace.path
+            );
+        }
+
         let mut tenants = HashMap::with_capacity(config.num_tenants as usize);
         for i in 1..config.num_tenants + 1 {
-            match config.policy {
-                Policy::RoundRobin => {
-                    tenants.insert(
-                        i,
-                        Arc::new(RefCell::new(Tenant::new(
-                            i as u16,
-                            Box::new(RoundRobin::new()),
-                        ))),
-                    );
-                }
-                Policy::Minos => {
-                    tenants.insert(
-                        i,
-                        Arc::new(RefCell::new(Tenant::new(i as u16, Box::new(Minos::new())))),
-                    );
-                }
-            }
+            tenants.insert(i, Rc::new(RefCell::new(Tenant::new(i as u32, &config))));
+        }
+        let max_cores = config.num_cores as usize;
+        let num_tenants = config.num_tenants as usize;
+        let mut dispatcher = None;
+        if config.policy == Policy::Shinjuku {
+            dispatcher = Some(Shinjuku::new(&config));
         }
-        let max_cores = config.small_cores as usize;
-        let num_reqs = config.num_reqs as usize;
+        let owner = placement::place(&config);
+        let topology = Arc::new(Topology::new(&config, &owner));
+        let precision = config.histogram_precision;
+        let tenant_precision = config.tenant_histogram_precision;
 
-        Simulator {
-            config: config,
+        Ok(Simulator {
+            config,
             cores: Vec::with_capacity(max_cores),
-            latencies: Vec::with_capacity(max_cores * num_reqs),
-            tenants: tenants,
-        }
+            latencies: Histogram::new(precision),
+            tenants,
+            classes: [Histogram::new(precision), Histogram::new(precision)],
+            tenant_stats: vec![TenantStats::new(tenant_precision); num_tenants],
+            completed: 0,
+            window_start: None,
+            baseline: Vec::new(),
+            dispatcher,
+            dispatcher_baseline: None,
+            owner,
+            topology,
+            now: 0,
+            events: EventQueue::new(),
+            out_dir: None,
+            quiet: false,
+            trace,
+            clock,
+        })
+    }
+
+    /// This method makes the simulator also write its results to the given directory.
+    ///
+    /// # Arguments
+    /// `dir`: The directory for the results; it must already exist.
+    pub fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = Some(dir);
     }
 
     pub fn core_init(&mut self) {
-        assert_eq!(self.config.small_cores + self.config.large_cores, 32);
-
-        if self.config.large_cores != 0 {
-            assert_eq!(self.config.policy, Policy::Minos);
-            assert_eq!(
-                (((self.config.req_rate as f64
-                    * 0.001
-                    * consts::TASK_DISTRIBUTION_TIME[1]
-                    * self.config.small_cores as f64)
-                    / 1e6) as f64)
-                    .ceil(),
-                self.config.large_cores as f64
-            );
-        } else {
-            assert_eq!(self.config.policy, Policy::RoundRobin);
+        // Minos starts with the last cores as its large cores; by default as many as the expected
+        // load of the long requests needs.
+        let num_cores = self.config.num_cores as usize;
+        let mut num_large = 0;
+        if self.config.policy == Policy::Minos {
+            num_large = self.config.large_cores as usize;
+            if num_large == 0 {
+                num_large = match self.config.service_time.modes() {
+                    Some((short_us, long_us, long_fraction)) => {
+                        let long = long_us * long_fraction;
+                        large_cores(num_cores, long / (long + short_us * (1.0 - long_fraction)))
+                    }
+                    None => 1,
+                };
+            }
         }
 
-        for i in 0..self.config.small_cores {
+        let mut placed = vec![Vec::new(); num_cores];
+        for (t, core) in self.owner.iter().enumerate() {
+            placed[*core].push(t as u32 + 1);
+        }
+        for (i, placed) in placed.iter().enumerate() {
+            let mut core_type = CoreType::Small;
+            if i >= num_cores - num_large {
+                core_type = CoreType::Large;
+            }
             self.cores.push(Core::new(
-                i as u8,
+                i as u32,
                 &self.config,
-                self.config.small_cores,
+                self.config.num_cores,
                 &self.tenants,
-                CoreType::Small,
+                placed,
+                core_type,
+                &self.topology,
             ));
         }
+        if num_large > 0 {
+            info!("Minos starts with {} large cores", num_large);
+        }
 
-        // Start from zero as the partition is relative and won't work if started from absolute core-id.
-        for i in 0..self.config.large_cores {
-            self.cores.push(Core::new(
-                i as u8,
-                &self.config,
-                self.config.large_cores,
-                &self.tenants,
-                CoreType::Large,
-            ));
+        // Hand each recorded request to the core its tenant is placed on; the tenants which move
+        // later are routed from there.
+        if !self.config.trace.path.is_empty() {
+            for core in self.cores.iter_mut() {
+                core.trace = Some(VecDeque::new());
+            }
+            for r in self.trace.drain(..) {
+                let owner = self.owner[r.tenant as usize - 1];
+                self.cores[owner].trace.as_mut().unwrap().push_back(r);
+            }
         }
     }
 
     pub fn start(&mut self) {
         self.core_init();
-        loop {
-            // Run each core one by one.
-            for c in 0..(self.config.small_cores + self.config.large_cores) {
-                self.cores[c as usize].run();
-                let mut latency: Vec<u64> = self.cores[c as usize].latencies.drain(..).collect();
-                self.latencies.append(&mut latency);
+        self.check_warmup();
+
+        // Each core starts with the arrival of its first request.
+        for c in 0..self.cores.len() {
+            if let Some(time) = self.cores[c].next_arrival() {
+                self.events.push(Event::new(time, c, EventType::Arrival));
+            }
+        }
+        if let Some(time) = self.next_rebalance() {
+            let index = self.cores.len();
+            self.events
+                .push(Event::new(time, index, EventType::Rebalance));
+        }
+        if let Some(time) = self.next_partition() {
+            let index = self.cores.len();
+            self.events
+                .push(Event::new(time, index, EventType::Partition));
+        }
+
+        // Process the events of all the cores in time-stamp order on the global clock.
+        while let Some(event) = self.events.pop() {
+            self.now = event.time;
+
+            // Only the requests completed in the measurement window count towards the results;
+            // stop before the first event after the window changes any counter.
+            let now = self.now;
+            if self.window_end().is_some_and(|end| now > end) {
+                info!("Measurement window completed !!!\n");
+                return;
+            }
+
+            match event.event_type {
+                EventType::Dispatch => self.complete_dispatch(),
+
+                EventType::Rebalance => self.rebalance(),
+
+                EventType::Partition => self.partition(),
+
+                _ => {
+                    self.cores[event.core].handle(event, &mut self.events);
+                    self.route(event.core);
+                    if self.config.steal != Steal::NoStealing || self.config.policy == Policy::Minos
+                    {
+                        self.balance(event);
+                    }
+                }
+            }
+            if self.dispatcher.is_some() {
+                self.dispatch();
             }
 
-            // Check exit condition after each iteration.
+            if event.event_type != EventType::Completion {
+                continue;
+            }
+
+            let measure = self.window_start.is_some();
+            for sample in self.cores[event.core].latencies.drain(..) {
+                self.completed += 1;
+                if !measure {
+                    continue;
+                }
+                let long = sample.service_us > self.config.minos_threshold_us;
+                self.tenant_stats[sample.tenant as usize - 1].record(&sample, long, &self.clock);
+                self.classes[long as usize].record(sample.latency);
+                self.latencies.record(sample.latency);
+            }
+            self.check_warmup();
+
+            // Check exit condition after each completed request. When the requests move between
+            // the cores, the cores together must process the responses of all of them.
             let mut exit = true;
-            for c in 0..self.config.small_cores {
-                if self.config.num_resps > self.cores[c as usize].request_processed {
-                    exit = false;
+            let cores = &self.cores[..self.config.num_cores as usize];
+            if self.requests_move() {
+                let processed: u64 = cores.iter().map(|c| c.request_processed).sum();
+                exit = processed >= self.config.num_resps * self.config.num_cores;
+            } else {
+                for core in cores {
+                    if self.config.num_resps > core.request_processed {
+                        exit = false;
+                    }
                 }
             }
-            if exit == true {
+            if exit {
                 info!("Request generation completed !!!\n");
                 return;
             }
         }
+        info!("No more events to process at time {}\n", self.now);
+    }
+
+    // If the requests of a generating core may run on other cores.
+    fn requests_move(&self) -> bool {
+        self.config.steal != Steal::NoStealing
+            || self.config.policy == Policy::Minos
+            || self.dispatcher.is_some()
+            || self.config.placement != Placement::Contiguous
+            || self.config.rebalance.interval_us > 0.0
+    }
+
+    // Hand the new requests of the tenants placed on other cores to their cores.
+    fn route(&mut self, core: usize) {
+        if self.cores[core].routed.is_empty() {
+            return;
+        }
+
+        let now = self.now;
+        let routed: Vec<(u32, u64, f64)> = self.cores[core].routed.drain(..).collect();
+        for (tenant, rdtsc, task_time) in routed {
+            let owner = self.owner[tenant as usize - 1];
+            self.cores[owner].deliver(tenant, rdtsc, task_time, now, owner, &mut self.events);
+        }
+    }
+
+    // The time-stamp of the next rebalancing round; None if the rebalancer is disabled.
+    fn next_rebalance(&self) -> Option<u64> {
+        let interval = (self.config.rebalance.interval_us * self.clock.cycles_per_us()) as u64;
+        if interval == 0 {
+            return None;
+        }
+        Some(self.now + interval)
+    }
+
+    // Migrate tenants from the most to the least loaded cores, based on the service time of the
+    // requests which arrived since the last round.
+    fn rebalance(&mut self) {
+        let num_tenants = self.config.num_tenants as usize;
+        let mut load = vec![0.0; num_tenants];
+        let mut pinned = vec![false; num_tenants];
+        for t in 0..num_tenants {
+            let mut tenant = self.tenants[&(t as u64 + 1)].borrow_mut();
+            load[t] = tenant.demand_us;
+            tenant.demand_us = 0.0;
+            pinned[t] = self.cores[self.owner[t]].is_running(t as u32 + 1);
+        }
+
+        let rebalance = &self.config.rebalance;
+        let moves = placement::plan(
+            &load,
+            &self.owner,
+            &pinned,
+            self.config.num_cores as usize,
+            rebalance.threshold,
+            rebalance.max_migrations,
+        );
+        let now = self.now;
+        for (tenant, dst) in moves {
+            let src = self.owner[tenant as usize - 1];
+            let removed = self.cores[src].remove_tenant(tenant);
+            self.cores[dst].add_tenant(removed, now, dst, &mut self.events);
+            self.owner[tenant as usize - 1] = dst;
+        }
+
+        // Stop once the rest of the simulation has nothing left to do.
+        if !self.events.is_empty() {
+            if let Some(time) = self.next_rebalance() {
+                let index = self.cores.len();
+                self.events
+                    .push(Event::new(time, index, EventType::Rebalance));
+            }
+        }
+    }
+
+    // The time-stamp of the next Minos partitioning; None if the split of the cores is fixed.
+    fn next_partition(&self) -> Option<u64> {
+        let interval = (self.config.minos.interval_us * self.clock.cycles_per_us()) as u64;
+        if self.config.policy != Policy::Minos || interval == 0 {
+            return None;
+        }
+        Some(self.now + interval)
+    }
+
+    // Resize the large cores of Minos to the share of the long requests in the work which arrived
+    // since the last round; the last cores are the large ones.
+    fn partition(&mut self) {
+        let (mut total_us, mut long_us) = (0.0, 0.0);
+        for core in self.cores.iter_mut() {
+            let (total, long) = core.take_arrived();
+            total_us += total;
+            long_us += long;
+        }
+
+        if total_us > 0.0 {
+            let num_cores = self.cores.len();
+            let num_large = large_cores(num_cores, long_us / total_us);
+            let now = self.now;
+            let mut changed = false;
+            for i in 0..num_cores {
+                let (mut core_type, mut quanta_us) = (CoreType::Small, self.config.costs.quanta_us);
+                if i >= num_cores - num_large {
+                    core_type = CoreType::Large;
+                    quanta_us = self.config.costs.large_quanta_us;
+                }
+                if !self.config.preemption {
+                    quanta_us = f64::INFINITY;
+                }
+                if self.cores[i].core_type != core_type {
+                    changed = true;
+                    self.cores[i].set_core_type(core_type, quanta_us, now, i, &mut self.events);
+                }
+            }
+
+            // The cores which changed their type may now take the requests of the other cores.
+            if changed {
+                debug!("Minos moves to {} large cores", num_large);
+                for i in 0..num_cores {
+                    if self.cores[i].is_idle() {
+                        self.steal_for(i);
+                    }
+                }
+            }
+        }
+
+        // Stop once the rest of the simulation has nothing left to do.
+        if !self.events.is_empty() {
+            if let Some(time) = self.next_partition() {
+                let index = self.cores.len();
+                self.events
+                    .push(Event::new(time, index, EventType::Partition));
+            }
+        }
+    }
+
+    // Move the new and the preempted requests of the workers to the global queue, and hand the
+    // request at its head to an idle worker; preferably one which already runs its tenant.
+    fn dispatch(&mut self) {
+        let dispatcher = match self.dispatcher.as_mut() {
+            Some(dispatcher) => dispatcher,
+            None => return,
+        };
+        for core in self.cores.iter_mut() {
+            for req in core.outbox.drain(..) {
+                dispatcher.enqueue(req);
+            }
+        }
+
+        if !dispatcher.is_ready() {
+            return;
+        }
+        let tenant = dispatcher.next_tenant();
+        let cores = &self.cores;
+        let worker = cores
+            .iter()
+            .position(|c| c.is_idle() && c.active_tenant == tenant)
+            .or_else(|| cores.iter().position(|c| c.is_idle()));
+        if let Some(worker) = worker {
+            let time = dispatcher.dispatch(worker, self.now);
+            let index = self.cores.len();
+            self.events
+                .push(Event::new(time, index, EventType::Dispatch));
+        }
+    }
+
+    // The dispatcher has handed a request to a worker; the worker starts running it.
+    fn complete_dispatch(&mut self) {
+        let (req, worker) = self.dispatcher.as_mut().unwrap().complete();
+        let now = self.now;
+        self.cores[worker].accept(vec![req], 0, now, worker, &mut self.events);
+    }
+
+    // Let the idle cores steal the requests waiting on the other cores. An idle core polls the
+    // other run-queues, so it steals as soon as new requests arrive on a busy core. With Minos,
+    // the small and the large cores take the requests of their type from each other.
+    fn balance(&mut self, event: Event) {
+        let minos = self.config.policy == Policy::Minos;
+        if self.cores[event.core].is_idle() {
+            self.steal_for(event.core);
+        } else if event.event_type == EventType::Arrival {
+            let num_cores = self.cores.len();
+            for i in 1..num_cores {
+                let thief = (event.core + i) % num_cores;
+                if self.cores[thief].is_idle() && !self.steal_for(thief) && !minos {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Move requests to the idle core `thief` from the closest core with waiting requests; the
+    // one with the most of them among the equally close ones. Returns false if there was nothing
+    // to steal. Minos hands the requests over on the same server, so it doesn't pay for the steal.
+    fn steal_for(&mut self, thief: usize) -> bool {
+        let minos = self.config.policy == Policy::Minos;
+        let core_type = self.cores[thief].core_type;
+        let mut victim = None;
+        let mut best = (usize::MAX, 0);
+        for (i, core) in self.cores.iter().enumerate() {
+            if i == thief || (minos && core.core_type == core_type) {
+                continue;
+            }
+            let stealable = core.stealable();
+            let distance = self.topology.distance(thief, i);
+            if stealable > 0 && (distance < best.0 || (distance == best.0 && stealable > best.1)) {
+                best = (distance, stealable);
+                victim = Some(i);
+            }
+        }
+
+        let victim = match victim {
+            Some(victim) => victim,
+            None => return false,
+        };
+        let stolen = self.cores[victim].steal(&self.config.steal);
+        if stolen.is_empty() {
+            return false;
+        }
+        let now = self.now;
+        let mut cycles = self.config.steal_cycles;
+        if minos {
+            cycles = 0;
+        }
+        self.cores[thief].num_steals += 1;
+        self.cores[thief].accept(stolen, cycles, now, thief, &mut self.events);
+        true
+    }
+
+    // Start the measurement window once both the warm-up requests and the warm-up time are over.
+    fn check_warmup(&mut self) {
+        if self.window_start.is_some()
+            || self.completed < self.config.warmup_requests
+            || (self.now as f64) < self.config.warmup_time_us * self.clock.cycles_per_us()
+        {
+            return;
+        }
+
+        if self.completed > 0 {
+            info!("Warm-up completed after {} requests", self.completed);
+        }
+        self.window_start = Some(self.now);
+        self.baseline = self.cores.iter().map(|core| core.report()).collect();
+        let now = self.now;
+        self.dispatcher_baseline = self.dispatcher.as_ref().map(|d| d.report(now));
+    }
+
+    // The time-stamp at which the measurement window ends; None during the warm-up and when the
+    // window lasts until the end of the run.
+    fn window_end(&self) -> Option<u64> {
+        let window_len = (self.config.measure_duration_us * self.clock.cycles_per_us()) as u64;
+        match self.window_start {
+            Some(start) if window_len > 0 => Some(start + window_len),
+            _ => None,
+        }
+    }
+
+    // The start and the end of the measurement window so far.
+    fn window(&self) -> (u64, u64) {
+        let start = self.window_start.unwrap_or(self.now);
+        let end = self.window_end().map_or(self.now, |end| end.min(self.now));
+        (start, end)
+    }
+
+    // The counters of each core over the measurement window.
+    fn core_reports(&self) -> Vec<CoreReport> {
+        let (start, end) = self.window();
+        let mut reports = Vec::with_capacity(self.cores.len());
+        for (i, core) in self.cores.iter().enumerate() {
+            let report = core.report();
+            reports.push(match self.baseline.get(i) {
+                Some(baseline) => report.since(baseline, start, end, &self.clock),
+                None => report.since(&report, start, end, &self.clock),
+            });
+        }
+        reports
     }
 }
 
 pub struct Core {
     // The id of the core.
-    pub core_id: u8,
+    pub core_id: u32,
 
     // This tenant is active on this core.
-    pub active_tenant: Option<u16>,
+    pub active_tenant: Option<u32>,
 
-    // The simulated time-stamp per core.
+    // The time-stamp of the last event processed by this core, on the global clock.
     pub rdtsc: u64,
 
     // The number of requests processed by this core;
     pub request_processed: u64,
 
-    // The latency for each request.
-    pub latencies: Vec<u64>,
+    // The requests completed since the simulator last collected them.
+    pub latencies: Vec<Sample>,
 
     // The dispather generates the requests for each core.
     pub dispatcher: Dispatch,
 
-    // Starting tenant-id which this core handles.
-    pub start_tenant: u16,
+    // The recorded requests to replay on this core, instead of the ones from the dispatcher.
+    pub trace: Option<VecDeque<TraceRecord>>,
+
+    // Starting tenant-id which this core generates the requests for.
+    pub start_tenant: u32,
 
-    // Last tenant-id which this core handles.
-    pub end_tenant: u16,
+    // Last tenant-id which this core generates the requests for; exclusive.
+    pub end_tenant: u32,
 
     // Total number of context switches per core.
     pub num_context_switches: u64,
@@ -183,227 +628,443 @@ pub struct Core {
     // Total number of preemptions per core.
     pub num_preemptions: u64,
 
+    // Total number of times this core stole requests from another core.
+    pub num_steals: u64,
+
+    // Total number of tenants migrated to this core.
+    pub num_migrations: u64,
+
+    // Total number of switches to a tenant without an MPK key or an EPTP list entry.
+    pub num_key_misses: u64,
+
+    // Total number of switches to a tenant whose memory is on another NUMA node.
+    pub num_numa_switches: u64,
+
+    // Total CPU cycles spent on the switches to the tenants on other NUMA nodes.
+    numa_cycles: u64,
+
+    // The NUMA nodes of the cores and of the memory of the tenants; None for the processor
+    // sharing baseline, which has no switch costs.
+    topology: Option<Arc<Topology>>,
+
+    // The MPK keys or the EPTP list entries of the tenants of this core; only if they are shared
+    // by all the tenants of the core.
+    keys: Option<KeyCache>,
+
+    // The cost of moving an MPK key or an EPTP list entry to another tenant in CPU cycles.
+    miss_cycles: u64,
+
+    // The cost of a switch with the isolation mechanism of this core in CPU cycles; including the
+    // TLB refill after a VMFUNC switch without tagging.
+    switch_cycles: u64,
+
+    // The cost of a full context-switch between address spaces in CPU cycles.
+    paging_cycles: u64,
+
+    // The cost of one preemption in CPU cycles.
+    preemption_cycles: u64,
+
+    // The scheduler time quanta of this core in micro-seconds.
+    quanta_us: f64,
+
+    // The virtual clock, at the frequency of the configuration.
+    clock: Clock,
+
+    // The cache and the TLB of this core; only if the misses after a switch are modelled.
+    cache: Option<CacheModel>,
+
+    // The CPU cycles the next task loses to cache and TLB misses.
+    cold_next: u64,
+
+    // Total CPU cycles lost to cache and TLB misses after the switches.
+    pub cold_cycles: u64,
+
+    // The cost of taking in a migrated tenant in CPU cycles.
+    migration_cycles: u64,
+
+    // The cost of one steal in CPU cycles.
+    steal_cycles: u64,
+
+    // The CPU cycles to spend before the next task starts; e.g. for a steal.
+    pending_cycles: u64,
+
+    // The requests of the tenants of other cores, stolen or handed over by the central
+    // dispatcher; they run before the ones of this core.
+    remote: VecDeque<Box<Request>>,
+
+    // The requests for the central dispatcher; new ones and the preempted ones.
+    pub outbox: Vec<Box<Request>>,
+
+    // The new requests of the tenants placed on other cores; the tenant-id, the arrival
+    // time-stamp and the service time.
+    pub routed: Vec<(u32, u64, f64)>,
+
+    // A central dispatcher owns the requests; the core only runs the ones handed to it.
+    centralized: bool,
+
+    // The CPU cycles spent running the requests on this core.
+    pub busy_cycles: u64,
+
     // Isolation mechanism amoung domains on a core.
     pub isolation: Isolation,
 
-    // Tenant vector, which holds the reference to tenants for a particular core.
-    pub tenants: Vec<Arc<RefCell<Tenant>>>,
+    // Tenant vector, which holds the reference to tenants for a particular core; ordered by
+    // tenant-id.
+    pub tenants: Vec<Rc<RefCell<Tenant>>>,
 
-    // Batch size used by the core/scheduler.
-    batch_size: usize,
+    // The index of each tenant of this core in `tenants`.
+    index: HashMap<u32, usize>,
+
+    // The scheduler of this core; it decides which request of its tenants runs next. It gets a
+    // view of the core on each decision, hence the RefCell.
+    sched: RefCell<Box<dyn Scheduler>>,
 
     // Distribution mechanism amoung tenants on a core.
     pub distribution: Dist,
 
-    // Range for MPK domains.
-    pub mpk_domains: Vec<Range<u16>>,
-
-    // Range for VMFunc domains.
-    pub vmfunc_domains: Vec<Range<u16>>,
+    // The number of consecutive tenants in `tenants` which share an MPK or VMFUNC domain; all of
+    // them with the virtualized keys.
+    domain_size: usize,
 
     // Outstanding tasks in the queue.
     outstanding: usize,
 
-    // Distribution of short-running and long-running tasks.
-    pub task_distribution: WeightedIndex<f64>,
+    // The task running on this core; along with false for a remote task, whose tenant isn't on
+    // this core.
+    current: Option<(Box<Request>, bool)>,
+
+    // The service time distributions of the tenants on this core.
+    service: ServiceModel,
 
     // Random number generator.
-    rng: Box<ThreadRng>,
+    rng: Box<StdRng>,
 
     // The last completed or preempted in the middle.
     last_task_state: TaskState,
 
-    // Type of the core; Small or Large.
+    // Type of the core; Small or Large. Minos changes it at runtime.
     core_type: CoreType,
 
-    // If the cores are partitioned between large and small cores.
-    is_core_partitioned: bool,
+    // Requests longer than this, in micro-seconds, are long requests.
+    long_threshold_us: f64,
+
+    // The service time, in micro-seconds, of the requests which arrived on this core since the
+    // last Minos partitioning; of all of them and of the long ones.
+    arrived_us: f64,
+    arrived_long_us: f64,
 }
 
 impl Core {
+    /// This method creates a core.
+    ///
+    /// # Arguments
+    /// `id`: The index of the core.
+    /// `config`: The simulator configuration.
+    /// `num_cores`: The number of cores.
+    /// `tenants`: All the tenants, by tenant-id.
+    /// `placed`: The tenant-ids which run on this core, in increasing order.
+    /// `coretype`: Small or Large.
+    /// `topology`: The sockets, NUMA nodes and SMT siblings of the cores.
     pub fn new(
-        id: u8,
+        id: u32,
         config: &Config,
         num_cores: u64,
-        tenants: &HashMap<u64, Arc<RefCell<Tenant>>>,
+        tenants: &HashMap<u64, Rc<RefCell<Tenant>>>,
+        placed: &[u32],
         coretype: CoreType,
+        topology: &Arc<Topology>,
     ) -> Core {
-        let uniform_divide: u16 = config.num_tenants as u16 / num_cores as u16;
-        let low = (id as u16 * uniform_divide) + 1 as u16;
-        let mut high = low + uniform_divide as u16;
-        if id == num_cores as u8 - 1 {
-            high = config.num_tenants as u16 + 1;
-        }
-
-        // Partition tenants in MPK Domains.
-        let tenants_per_domain = 15;
-        let num_domains = (high - low) / tenants_per_domain;
-        let mut mpkdomains = Vec::with_capacity(num_domains as usize);
-
-        let mut mpk_low = low;
-        while mpk_low < high {
-            let mpk_high = min(mpk_low + tenants_per_domain, high);
-            mpkdomains.push(Range {
-                start: mpk_low,
-                end: mpk_high,
-            });
-            mpk_low = mpk_high;
+        // The core generates the requests of a contiguous range of tenants, wherever they run.
+        let range = placement::contiguous(config.num_tenants, num_cores, id as u64);
+        let (low, high) = (range.start, range.end);
+
+        // Intialize the tenants and assign these tenants to this core.
+        let mut tenants_vec: Vec<Rc<RefCell<Tenant>>> = Vec::with_capacity(placed.len());
+        for i in placed {
+            let tenant = tenants.get(&(*i as u64)).unwrap();
+            tenants_vec.push(Rc::clone(tenant));
         }
 
-        // Partition tenants in VMFUNC Domains.
-        let tenants_per_domain = 512;
-        let num_domains = (high - low) / tenants_per_domain;
-        let mut vmdomains = Vec::with_capacity(num_domains as usize);
+        let mut batch_size = 1;
+        if config.batching {
+            batch_size = config.costs.batch_size;
+        }
 
-        let mut vm_low = low;
-        while vm_low < high {
-            let vm_high = min(vm_low + tenants_per_domain, high);
-            vmdomains.push(Range {
-                start: vm_low,
-                end: vm_high,
-            });
-            vm_low = vm_high;
+        // All the tenants of the core share the virtualized keys of one address space, or the
+        // entries of the EPTP list.
+        let (keys, mut miss_cycles) = match config.isolation {
+            Isolation::MpkIsolation if config.mpk.policy != KeyPolicy::Static => (
+                Some(KeyCache::new(config.mpk.keys as usize, config.mpk.policy)),
+                config.mpk.retag_cycles,
+            ),
+            Isolation::VmfuncIsolation if config.vmfunc.policy != KeyPolicy::Static => (
+                Some(KeyCache::new(
+                    config.vmfunc.eptp_entries as usize,
+                    config.vmfunc.policy,
+                )),
+                config.vmfunc.reload_cycles,
+            ),
+            _ => (None, 0),
+        };
+
+        let mut domain_size = placement::domain_size(config);
+        if keys.is_some() {
+            domain_size = usize::MAX;
         }
 
-        // Intialize the tenants and assign these tenants to this core.
-        let mut tenants_vec: Vec<Arc<RefCell<Tenant>>> = Vec::with_capacity((high - low) as usize);
-        for i in low..high {
-            let tenant = tenants.get(&(i as u64)).unwrap();
-            tenants_vec.push(Arc::clone(tenant));
+        let mut cache = None;
+        if config.cache.enabled {
+            cache = Some(CacheModel::new(&config.cache));
         }
 
-        let mut batch_size = 1;
-        if config.batching == true {
-            batch_size = consts::BATCH_SIZE;
+        let costs = &config.costs;
+        let centralized = config.policy == Policy::Shinjuku;
+        let mut switch_cycles = costs.switch_cycles(&config.isolation);
+        if config.isolation == Isolation::VmfuncIsolation && !config.vmfunc.tagged {
+            switch_cycles += config.vmfunc.flush_cycles;
         }
 
-        let mut updated_id = id;
-        let mut req_rate = config.req_rate;
-        let mut num_reqs = config.num_reqs;
-        match coretype {
-            CoreType::Large => {
-                updated_id = id + config.small_cores as u8;
-                req_rate = (config.req_rate as f64 * 0.001 * config.small_cores as f64) as u64
-                    / config.large_cores;
-                num_reqs = (config.num_reqs as f64 * 0.001 * config.small_cores as f64) as u64
-                    / config.large_cores;
-            }
-            _ => {}
+        let mut paging_cycles = costs.switch_cycles(&Isolation::PageTableIsolation);
+        let mut preemption_cycles = costs.preemption_cycles(&config.isolation, centralized);
+
+        let mut quanta_us = costs.quanta_us;
+        if coretype == CoreType::Large {
+            quanta_us = costs.large_quanta_us;
         }
 
-        let mut is_core_partitioned = false;
-        if config.large_cores > 0 {
-            is_core_partitioned = true;
+        // Without preemption, the quanta never expires and each request runs to completion.
+        if !config.preemption {
+            quanta_us = f64::INFINITY;
+        }
+        let mut topology = Some(Arc::clone(topology));
+
+        // Processor sharing is the ideal baseline; the requests share the core without any
+        // overheads.
+        if config.policy == Policy::ProcessorSharing {
+            quanta_us = config.ps_quanta_us;
+            switch_cycles = 0;
+            paging_cycles = 0;
+            preemption_cycles = 0;
+            miss_cycles = 0;
+            cache = None;
+            topology = None;
         }
 
+        // The Shinjuku workers take the requests from the global queue; the run-queues of the
+        // tenants stay empty.
+        let sched: Box<dyn Scheduler> = match config.policy {
+            Policy::RoundRobin | Policy::Shinjuku => Box::new(RoundRobin::new(batch_size)),
+            Policy::Minos => Box::new(Minos::new(batch_size, config.minos_threshold_us)),
+            Policy::Srpt => Box::new(Srpt::new()),
+            Policy::Edf => Box::new(Edf::new(config.clock())),
+            Policy::Priority => Box::new(Priority::new(batch_size)),
+            Policy::ProcessorSharing => Box::new(ProcessorSharing::new()),
+            Policy::Drr => Box::new(Drr::new(config.drr_quantum_us, quanta_us)),
+            Policy::Wfq => Box::new(Wfq::new(quanta_us)),
+            Policy::Lottery => Box::new(Lottery::new(config.rng(id, 3))),
+            Policy::Stride => Box::new(Stride::new(quanta_us)),
+        };
+
         Core {
-            core_id: updated_id,
+            core_id: id,
             active_tenant: None,
             rdtsc: 0,
             request_processed: 0,
             latencies: Vec::with_capacity(batch_size),
-            dispatcher: Dispatch::new(config, low, high, req_rate, num_reqs),
+            dispatcher: Dispatch::new(config, low, high, config.req_rate, config.num_reqs, id),
+            trace: None,
             start_tenant: low,
             end_tenant: high,
             num_context_switches: 0,
             num_mpk_switches: 0,
             num_vmfunc_switches: 0,
             num_preemptions: 0,
+            num_steals: 0,
+            num_migrations: 0,
+            num_key_misses: 0,
+            num_numa_switches: 0,
+            numa_cycles: 0,
+            topology,
+            keys,
+            miss_cycles,
+            switch_cycles,
+            paging_cycles,
+            preemption_cycles,
+            quanta_us,
+            clock: config.clock(),
+            cache,
+            cold_next: 0,
+            cold_cycles: 0,
+            migration_cycles: config.rebalance.migration_cycles,
+            steal_cycles: config.steal_cycles,
+            pending_cycles: 0,
+            remote: VecDeque::new(),
+            outbox: Vec::new(),
+            routed: Vec::new(),
+            centralized,
+            busy_cycles: 0,
             isolation: config.isolation.clone(),
+            index: index_of(&tenants_vec),
             tenants: tenants_vec,
-            batch_size: batch_size,
+            sched: RefCell::new(sched),
             distribution: config.distribution.clone(),
-            mpk_domains: mpkdomains,
-            vmfunc_domains: vmdomains,
+            domain_size,
             outstanding: 0,
-            task_distribution: WeightedIndex::new(vec![99.9, 0.1]).unwrap(),
-            rng: Box::new(thread_rng()),
+            current: None,
+            service: ServiceModel::new(config),
+            rng: Box::new(config.rng(id, 1)),
             last_task_state: TaskState::Completed,
             core_type: coretype,
-            is_core_partitioned: is_core_partitioned,
+            long_threshold_us: config.minos_threshold_us,
+            arrived_us: 0.0,
+            arrived_long_us: 0.0,
         }
     }
 
-    pub fn rdtsc(&self) -> u64 {
-        self.rdtsc.clone()
+    /// This method changes the type of the core; the next requests it runs are of the new type.
+    ///
+    /// # Arguments
+    /// `core_type`: Small or Large.
+    /// `quanta_us`: The time quanta of the cores of this type in micro-seconds.
+    /// `now`: The current time-stamp on the global clock.
+    /// `core`: The index of this core in the event queue.
+    /// `queue`: The global event queue.
+    pub fn set_core_type(
+        &mut self,
+        core_type: CoreType,
+        quanta_us: f64,
+        now: u64,
+        core: usize,
+        queue: &mut EventQueue,
+    ) {
+        self.core_type = core_type;
+        self.quanta_us = quanta_us;
+        self.wake(now, core, queue);
     }
 
-    pub fn update_rdtsc(&mut self) {
-        let next_dispatch_time = self.dispatcher.get_next();
-        if self.outstanding == 0 && self.rdtsc() < next_dispatch_time {
-            self.rdtsc = next_dispatch_time;
-        }
+    /// This method returns the service time of the requests which arrived on this core since the
+    /// last call, in micro-seconds.
+    ///
+    /// # Return
+    /// The service time of all the requests and of the long requests.
+    pub fn take_arrived(&mut self) -> (f64, f64) {
+        let arrived = (self.arrived_us, self.arrived_long_us);
+        self.arrived_us = 0.0;
+        self.arrived_long_us = 0.0;
+        arrived
+    }
+
+    pub fn rdtsc(&self) -> u64 {
+        self.rdtsc
     }
 
-    fn tenant_switch(&mut self, tenant: u16) {
+    /// This method switches the core to the given tenant.
+    ///
+    /// # Arguments
+    /// `tenant`: The tenant-id of the next tenant to run on this core.
+    /// `local`: False if the tenant belongs to another core, i.e. its request was stolen.
+    ///
+    /// # Return
+    /// The number of CPU cycles spent in the switch.
+    fn tenant_switch(&mut self, tenant: u32, local: bool) -> u64 {
+        let mut cycles = 0;
         if self.last_task_state == TaskState::Preempted {
             self.active_tenant = Some(tenant);
-            return;
+            if local {
+                cycles = self.assign_key(tenant);
+            }
+            return cycles;
         }
 
-        match self.isolation {
-            Isolation::NoIsolation => {
-                self.active_tenant = Some(tenant);
-                self.num_context_switches += 1;
+        // A stolen tenant is in none of the MPK or VMFUNC domains of this core; do a full
+        // context-switch unless there is no isolation at all.
+        if !local {
+            self.active_tenant = Some(tenant);
+            self.num_context_switches += 1;
+            match self.isolation {
+                Isolation::NoIsolation | Isolation::Custom(_) => cycles = self.switch_cycles,
+                _ => cycles = self.paging_cycles,
             }
+            return cycles;
+        }
 
-            Isolation::PageTableIsolation => {
+        match self.isolation {
+            Isolation::NoIsolation | Isolation::PageTableIsolation | Isolation::Custom(_) => {
                 self.active_tenant = Some(tenant);
-                self.rdtsc += consts::PAGING_TENANT_SWITCH_CYCLES;
+                cycles = self.switch_cycles;
                 self.num_context_switches += 1;
             }
 
             Isolation::MpkIsolation => {
-                // If this is not the starting of the scheduler, then some tenant must be active.
-                // Otherwise, do a full context-switch to run the tenant process on this core.
-                if let Some(curr_tenant) = self.active_tenant {
-                    for range in &self.mpk_domains {
-                        if range.contains(&curr_tenant) {
-                            // If the new tenant is in same MPK Domain as old tenant then do the
-                            // light-weight MPK domain switch; otherwise do full context-switch.
-                            if tenant < range.end {
-                                self.active_tenant = Some(tenant);
-                                self.rdtsc += consts::MPK_TENANT_SWITCH_CYCLES;
-                                self.num_mpk_switches += 1;
-                            } else {
-                                self.active_tenant = Some(tenant);
-                                self.rdtsc += consts::PAGING_TENANT_SWITCH_CYCLES;
-                                self.num_context_switches += 1;
-                            }
-                        }
-                    }
+                // If the new tenant is in same MPK Domain as old tenant then do the light-weight
+                // MPK domain switch; otherwise do full context-switch.
+                if self.same_domain(tenant) {
+                    cycles = self.switch_cycles;
+                    self.num_mpk_switches += 1;
                 } else {
-                    self.active_tenant = Some(tenant);
-                    self.rdtsc += consts::PAGING_TENANT_SWITCH_CYCLES;
+                    cycles = self.paging_cycles;
                     self.num_context_switches += 1;
                 }
+                cycles += self.assign_key(tenant);
+                self.active_tenant = Some(tenant);
             }
 
             Isolation::VmfuncIsolation => {
-                if let Some(curr_tenant) = self.active_tenant {
-                    for range in &self.vmfunc_domains {
-                        if range.contains(&curr_tenant) {
-                            if tenant < range.end {
-                                self.active_tenant = Some(tenant);
-                                self.rdtsc += consts::VMFUNC_TENANT_SWITCH_CYCLES;
-                                self.num_vmfunc_switches += 1;
-                            } else {
-                                self.active_tenant = Some(tenant);
-                                self.rdtsc += consts::PAGING_TENANT_SWITCH_CYCLES;
-                                self.num_context_switches += 1;
-                            }
-                        }
-                    }
+                if self.same_domain(tenant) {
+                    cycles = self.switch_cycles;
+                    self.num_vmfunc_switches += 1;
                 } else {
-                    self.active_tenant = Some(tenant);
-                    self.rdtsc += consts::PAGING_TENANT_SWITCH_CYCLES;
+                    cycles = self.paging_cycles;
                     self.num_context_switches += 1;
                 }
+                cycles += self.assign_key(tenant);
+                self.active_tenant = Some(tenant);
             }
         }
+        cycles
+    }
+
+    // The CPU cycles a switch to the tenant costs extra when its memory is on another NUMA node.
+    fn numa_switch(&mut self, tenant: u32) -> u64 {
+        let cycles = match self.topology.as_ref() {
+            Some(topology) => topology.switch_cycles(self.core_id as usize, tenant),
+            None => 0,
+        };
+        if cycles > 0 {
+            self.num_numa_switches += 1;
+            self.numa_cycles += cycles;
+        }
+        cycles
+    }
+
+    // If the tenant shares an isolation domain with the active tenant; the consecutive tenants of
+    // the core are grouped in domains of `domain_size`. At the start of the scheduler there is no
+    // active tenant, so the first switch is a full context-switch.
+    fn same_domain(&self, tenant: u32) -> bool {
+        let domain = |t: u32| self.index.get(&t).map(|i| i / self.domain_size);
+        match self.active_tenant {
+            Some(curr_tenant) => {
+                domain(curr_tenant).is_some() && domain(curr_tenant) == domain(tenant)
+            }
+            None => false,
+        }
+    }
+
+    // Give the tenant an MPK key or an EPTP list entry, if they are shared; on a miss, the pages
+    // of the tenant which loses the key are re-tagged, or its EPT is replaced in the list.
+    fn assign_key(&mut self, tenant: u32) -> u64 {
+        let hit = match self.keys.as_mut() {
+            Some(keys) => keys.access(tenant),
+            None => true,
+        };
+        if hit {
+            return 0;
+        }
+        self.num_key_misses += 1;
+        self.miss_cycles
     }
 
-    pub fn generate_req(&mut self) -> Option<u16> {
+    pub fn generate_req(&mut self) -> Option<u32> {
         if let Some(t) = self.dispatcher.generate_request(self.rdtsc()) {
             let tenant;
             match self.distribution {
@@ -430,144 +1091,864 @@ impl Core {
         }
     }
 
-    pub fn get_tenant_limit(&self) -> (u16, u16) {
+    pub fn get_tenant_limit(&self) -> (u32, u32) {
         (self.start_tenant, self.end_tenant)
     }
 
-    pub fn process_request(&mut self, mut req: Box<Request>, index: usize) {
-        let tenant = req.get_tenant();
-        if Some(tenant) != self.active_tenant {
-            self.tenant_switch(tenant);
-        }
+    /// This method handles an event for this core and schedules the events which follow it.
+    ///
+    /// # Arguments
+    /// `event`: The event to handle; its time-stamp becomes the current time on this core.
+    /// `queue`: The global event queue, used to schedule the upcoming events for this core.
+    pub fn handle(&mut self, event: Event, queue: &mut EventQueue) {
+        self.rdtsc = event.time;
+        match event.event_type {
+            EventType::Arrival => {
+                self.run_dispatcher();
+                if let Some(next) = self.next_arrival() {
+                    queue.push(Event::new(next, event.core, EventType::Arrival));
+                }
+            }
 
-        let (time, taskstate) = req.run(&self.isolation, self.core_type);
-        self.rdtsc += time;
-        match taskstate {
-            TaskState::Completed => {
-                let latency = self.rdtsc() - req.start_time();
-                self.latencies.push(latency);
+            EventType::SwitchDone => {
+                self.run_task(event.core, queue);
+                return;
+            }
+
+            EventType::Dispatch | EventType::Rebalance | EventType::Partition => {
+                panic!("The simulator handles its own events");
+            }
+
+            EventType::Completion => {
+                let (req, _local) = self.current.take().expect("Completion without a task");
+                self.latencies.push(Sample {
+                    tenant: req.get_tenant(),
+                    latency: self.rdtsc() - req.start_time(),
+                    service_us: req.max_time(),
+                });
                 self.request_processed += 1;
                 self.outstanding -= 1;
-                self.last_task_state = taskstate;
+                self.last_task_state = TaskState::Completed;
+                self.sched.borrow_mut().on_completion(&self.context(), &req);
+
+                if self.core_id == 0 && self.request_processed.is_multiple_of(2000000) {
+                    info!("Requests Processed {}", self.request_processed);
+                }
             }
 
-            TaskState::Preempted => {
+            EventType::QuantumExpiry => {
+                let (req, local) = self.current.take().expect("Preemption without a task");
                 self.num_preemptions += 1;
-                self.tenants[index].borrow_mut().enqueue_task(req);
-                self.last_task_state = taskstate;
+                match local {
+                    true => {
+                        let index = self.index[&req.get_tenant()];
+                        self.sched
+                            .borrow_mut()
+                            .on_quantum_expiry(&self.context(), index, req);
+                    }
+                    false if self.centralized => {
+                        self.outbox.push(req);
+                        self.outstanding -= 1;
+                    }
+                    false => self.remote.push_back(req),
+                }
+                self.last_task_state = TaskState::Preempted;
             }
+        }
 
-            TaskState::Runnable | TaskState::Running => {
-                println!("The task shouldn't return this state");
-            }
+        // The core is idle; start the next task, if there is one.
+        if self.current.is_none() {
+            self.schedule(event.core, queue);
         }
+    }
 
-        if self.core_id == 0 && self.request_processed % 2000000 == 0 {
-            info!("Requests Processed {}", self.request_processed);
+    /// This method returns the time-stamp of the next request arrival on this core, if any.
+    pub fn next_arrival(&self) -> Option<u64> {
+        match &self.trace {
+            Some(trace) => trace.front().map(|r| r.time),
+            None if self.dispatcher.has_next() => Some(self.dispatcher.get_next()),
+            None => None,
         }
     }
 
     fn run_dispatcher(&mut self) {
+        // Replay the recorded requests which have arrived by now.
+        let now = self.rdtsc;
+        if let Some(trace) = self.trace.as_mut() {
+            let mut arrived = Vec::new();
+            while trace.front().is_some_and(|r| r.time <= now) {
+                arrived.push(trace.pop_front().unwrap());
+            }
+            for r in arrived {
+                self.add_request(r.tenant as u32, r.time, r.service_us);
+            }
+            return;
+        }
+
         while let Some(tenant_id) = self.generate_req() {
-            let dindex = self.task_distribution.sample(&mut *self.rng);
-            let mut task_time = consts::TASK_DISTRIBUTION_TIME[dindex];
-            if self.is_core_partitioned == true {
-                match self.core_type {
-                    CoreType::Small => task_time = consts::TASK_DISTRIBUTION_TIME[0],
-
-                    CoreType::Large => {
-                        task_time = consts::TASK_DISTRIBUTION_TIME[1];
-                    }
+            let task_time = self.service.sample(tenant_id, &mut self.rng);
+            self.add_request(tenant_id, self.rdtsc, task_time);
+        }
+    }
+
+    // Queue a new request; it goes to the global queue if there is a central dispatcher, and to
+    // the core of its tenant if the tenant runs on another core.
+    fn add_request(&mut self, tenant_id: u32, rdtsc: u64, task_time: f64) {
+        if self.centralized {
+            let req = Request::new(tenant_id, rdtsc, task_time);
+            self.outbox.push(Box::new(req));
+            return;
+        }
+
+        match self.index.get(&tenant_id) {
+            Some(index) => {
+                let req = Box::new(Request::new(tenant_id, rdtsc, task_time));
+                self.tenants[*index].borrow_mut().demand_us += task_time;
+                self.arrived_us += task_time;
+                if task_time > self.long_threshold_us {
+                    self.arrived_long_us += task_time;
                 }
+                self.sched
+                    .borrow_mut()
+                    .on_arrival(&self.context(), *index, req);
+                self.outstanding += 1;
             }
-            let index = tenant_id as usize - self.start_tenant as usize;
-            self.tenants[index]
-                .borrow_mut()
-                .add_request(self.rdtsc, task_time);
-            self.outstanding += 1;
+            None => self.routed.push((tenant_id, rdtsc, task_time)),
         }
     }
 
-    pub fn run(&mut self) {
-        let (low, high) = self.get_tenant_limit();
+    // The remote tasks go first, as they have already waited on another core; then the one the
+    // scheduler picks.
+    fn pick_next_task(&mut self) -> Option<(Box<Request>, bool)> {
+        if let Some(task) = self.remote.pop_front() {
+            return Some((task, false));
+        }
+        let task = self.sched.borrow_mut().pick_next_task(&self.context());
+        task.map(|task| (task, true))
+    }
 
-        // Go through each tenant one by one; executing BATCH_SIZE tasks at a time.
-        for t in low..high {
-            let index: usize = (t - low) as usize;
-            for _t in 0..self.batch_size {
-                // Generate some more requests.
-                self.run_dispatcher();
+    // The view of this core for its scheduler.
+    fn context(&self) -> Context<'_> {
+        Context {
+            now: self.rdtsc,
+            core_id: self.core_id,
+            core_type: self.core_type,
+            tenants: &self.tenants,
+            active_tenant: self.active_tenant,
+            outstanding: self.outstanding,
+            remote: self.remote.len(),
+        }
+    }
 
-                let task = self.tenants[index].borrow_mut().get_request(self.core_type);
-                if let Some(task) = task {
-                    self.process_request(task, index);
-                } else {
-                    break;
-                }
+    fn schedule(&mut self, core: usize, queue: &mut EventQueue) {
+        if let Some((task, local)) = self.pick_next_task() {
+            let tenant = task.get_tenant();
+            let mut switch_cycles = 0;
+            let switches = self.num_context_switches;
+            if Some(tenant) != self.active_tenant {
+                self.sched
+                    .borrow_mut()
+                    .on_tenant_switch(&self.context(), tenant);
+                switch_cycles = self.tenant_switch(tenant, local);
+                switch_cycles += self.numa_switch(tenant);
+            }
+
+            // A context-switch moves to the page table of another address space, unless there is
+            // no isolation at all.
+            if let Some(cache) = self.cache.as_mut() {
+                let new_page_table = match self.isolation {
+                    Isolation::NoIsolation => false,
+                    _ => self.num_context_switches > switches,
+                };
+                self.cold_next = cache.warm(tenant, new_page_table);
+            }
+            switch_cycles += self.pending_cycles;
+            self.pending_cycles = 0;
+            self.current = Some((task, local));
+
+            if switch_cycles > 0 {
+                let time = self.rdtsc() + switch_cycles;
+                queue.push(Event::new(time, core, EventType::SwitchDone));
+            } else {
+                self.run_task(core, queue);
             }
         }
+    }
 
-        // Update the timestamp counter
-        self.update_rdtsc();
+    /// This method tells if the core has nothing to run; with Minos, the requests of the other
+    /// core type may still wait on it.
+    pub fn is_idle(&self) -> bool {
+        let foreign = self.sched.borrow().stealable(&self.context()).unwrap_or(0);
+        self.current.is_none() && self.outstanding == foreign
     }
-}
 
-impl Drop for Simulator {
-    fn drop(&mut self) {
-        // Calculate & print median & tail latency only on the master thread.
-        self.latencies.sort();
+    /// The number of requests waiting in the run-queues of the tenants of this core, which
+    /// another core can take.
+    pub fn stealable(&self) -> usize {
+        let ctx = self.context();
+        match self.sched.borrow().stealable(&ctx) {
+            Some(stealable) => stealable,
+            None => self.outstanding - self.current.is_some() as usize - self.remote.len(),
+        }
+    }
+
+    /// This method removes runnable requests, for an idle core to run them. The tenant which
+    /// this core would serve next is picked first.
+    ///
+    /// # Arguments
+    /// `steal`: RequestStealing takes one request; TenantStealing takes all of the tenant.
+    ///
+    /// # Return
+    /// The stolen requests; all of the same tenant.
+    pub fn steal(&mut self, steal: &Steal) -> Vec<Box<Request>> {
+        let all = *steal == Steal::TenantStealing;
+        let stolen = self.sched.borrow_mut().steal_task(&self.context(), all);
+        self.outstanding -= stolen.len();
+        stolen
+    }
+
+    /// This method hands requests of the tenants of other cores to this idle core, and starts
+    /// running them; they were stolen or handed over by the central dispatcher.
+    ///
+    /// # Arguments
+    /// `requests`: The requests to run.
+    /// `cycles`: The CPU cycles to spend before the first request starts; e.g. for the steal.
+    /// `now`: The current time-stamp on the global clock.
+    /// `core`: The index of this core in the event queue.
+    /// `queue`: The global event queue.
+    pub fn accept(
+        &mut self,
+        requests: Vec<Box<Request>>,
+        cycles: u64,
+        now: u64,
+        core: usize,
+        queue: &mut EventQueue,
+    ) {
+        self.outstanding += requests.len();
+        self.remote.extend(requests);
+        self.pending_cycles += cycles;
+        self.wake(now, core, queue);
+    }
+
+    /// This method queues a request of one of the tenants of this core, which arrived on another
+    /// core, and starts running it if the core is idle.
+    ///
+    /// # Arguments
+    /// `tenant`: The tenant-id of the request.
+    /// `rdtsc`: The time-stamp at which the request arrived.
+    /// `task_time`: The service time of the request in micro-seconds.
+    /// `now`: The current time-stamp on the global clock.
+    /// `core`: The index of this core in the event queue.
+    /// `queue`: The global event queue.
+    pub fn deliver(
+        &mut self,
+        tenant: u32,
+        rdtsc: u64,
+        task_time: f64,
+        now: u64,
+        core: usize,
+        queue: &mut EventQueue,
+    ) {
+        self.add_request(tenant, rdtsc, task_time);
+        self.wake(now, core, queue);
+    }
+
+    // Start the next task if the core is idle; it has been idle until `now`.
+    fn wake(&mut self, now: u64, core: usize, queue: &mut EventQueue) {
+        if self.current.is_none() {
+            self.rdtsc = now;
+            self.schedule(core, queue);
+        }
+    }
+
+    /// This method tells if a request of the given tenant is running, or about to run, on this
+    /// core; the tenant can't migrate until the request stops.
+    pub fn is_running(&self, tenant: u32) -> bool {
+        match &self.current {
+            Some((req, local)) => *local && req.get_tenant() == tenant,
+            None => false,
+        }
+    }
+
+    /// This method removes a tenant, along with its runnable requests, from this core.
+    ///
+    /// # Return
+    /// The tenant; it must not be running on this core.
+    pub fn remove_tenant(&mut self, tenant: u32) -> Rc<RefCell<Tenant>> {
+        let index = self.index[&tenant];
+        let removed = self.tenants.remove(index);
+        self.outstanding -= removed.borrow().queued();
+        if let Some(keys) = self.keys.as_mut() {
+            keys.forget(tenant);
+        }
+        self.index = index_of(&self.tenants);
+        self.sched
+            .borrow_mut()
+            .on_tenant_removed(&self.context(), index, &removed.borrow());
+        removed
+    }
+
+    /// This method adds a tenant migrated from another core, along with its runnable requests.
+    /// The migration cost is spent before the next task starts.
+    ///
+    /// # Arguments
+    /// `tenant`: The migrated tenant.
+    /// `now`: The current time-stamp on the global clock.
+    /// `core`: The index of this core in the event queue.
+    /// `queue`: The global event queue.
+    pub fn add_tenant(
+        &mut self,
+        tenant: Rc<RefCell<Tenant>>,
+        now: u64,
+        core: usize,
+        queue: &mut EventQueue,
+    ) {
+        let id = tenant.borrow().tenant_id;
+        let index = match self
+            .tenants
+            .binary_search_by_key(&id, |t| t.borrow().tenant_id)
+        {
+            Ok(index) | Err(index) => index,
+        };
+        self.outstanding += tenant.borrow().queued();
+        self.tenants.insert(index, tenant);
+        self.index = index_of(&self.tenants);
+        self.sched
+            .borrow_mut()
+            .on_tenant_added(&self.context(), index);
+
+        self.num_migrations += 1;
+        self.pending_cycles += self.migration_cycles;
+        self.wake(now, core, queue);
+    }
+
+    fn run_task(&mut self, core: usize, queue: &mut EventQueue) {
+        let (req, _local) = self.current.as_mut().expect("No task to run");
+        let remaining = req.remaining_time();
+        let cold_cycles = self.cold_next;
+        self.cold_next = 0;
+        self.cold_cycles += cold_cycles;
+        let (time, taskstate) = req.run(
+            self.quanta_us,
+            self.preemption_cycles,
+            cold_cycles,
+            &self.clock,
+        );
+
+        // Don't count the preemption overhead, which is part of `time`, or the cache and TLB
+        // misses, which are part of the remaining time, as busy cycles.
+        let mut ran = remaining;
+        if taskstate == TaskState::Preempted {
+            ran -= req.remaining_time();
+        }
+        self.busy_cycles += (ran * self.clock.cycles_per_us()) as u64;
+
+        match taskstate {
+            TaskState::Completed => {
+                queue.push(Event::new(self.rdtsc + time, core, EventType::Completion));
+            }
 
-        let m;
-        let t = self.latencies[(self.latencies.len() * 99) / 100];
-        match self.latencies.len() % 2 {
-            0 => {
-                let n = self.latencies.len();
-                m = (self.latencies[n / 2] + self.latencies[(n / 2) + 1]) / 2;
+            TaskState::Preempted => {
+                queue.push(Event::new(
+                    self.rdtsc + time,
+                    core,
+                    EventType::QuantumExpiry,
+                ));
             }
 
-            _ => m = self.latencies[self.latencies.len() / 2],
+            TaskState::Runnable | TaskState::Running => {
+                unreachable!("The task shouldn't return this state");
+            }
         }
-        println!(
-            "Latency: Median(us) {:.2} Tail(us) {:.2}",
-            cycles::to_seconds(m) * 1e6,
-            cycles::to_seconds(t) * 1e6,
+    }
+}
+
+// The number of large cores Minos needs for the long requests to take the given share of the
+// work; at least one core of each type.
+fn large_cores(num_cores: usize, long_share: f64) -> usize {
+    let num_large = (num_cores as f64 * long_share).ceil() as usize;
+    num_large.max(1).min(num_cores - 1)
+}
+
+// The index of each tenant in the vector, by tenant-id.
+fn index_of(tenants: &[Rc<RefCell<Tenant>>]) -> HashMap<u32, usize> {
+    tenants
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.borrow().tenant_id, i))
+        .collect()
+}
+
+impl Simulator {
+    /// This method returns the histogram of the latencies, in CPU cycles, of the requests
+    /// completed so far.
+    pub fn latencies(&self) -> &Histogram {
+        &self.latencies
+    }
+
+    /// This method computes the median and the 99th percentile latency in CPU cycles.
+    pub fn latency(&self) -> (u64, u64) {
+        (
+            self.latencies.median(),
+            self.latencies.value_at_quantile(0.99),
+        )
+    }
+
+    /// This method summarizes the run in one row, in the column layout used by `run-simulator`
+    /// and the plot scripts; Req-Rate, Cores, Tenants, Throughput, Median(us), Tail(us),
+    /// Context-Switches(%), Execution-Time(sec), CS-Time(sec) and Total-Time(sec).
+    pub fn row(&mut self) -> String {
+        let (m, t) = self.latency();
+        let max_cores = self.config.num_cores;
+
+        let (mut thrpt, mut csper, mut etime, mut cstime, mut ttime) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for core in self.core_reports() {
+            thrpt += core.throughput;
+            csper += core.context_switch_percent();
+            etime += core.requests as f64 / 1e6;
+            cstime += self.clock.to_seconds(core.overhead_cycles);
+            ttime += self.clock.to_seconds(core.total_cycles);
+        }
+
+        format!(
+            "{}, {}, {}, {:.0}, {:.2}, {:.2}, {:.2}, {:.2}, {:.2}, {:.2}",
+            self.config.req_rate * max_cores,
+            max_cores,
+            self.config.num_tenants,
+            thrpt,
+            self.clock.to_seconds(m) * 1e6,
+            self.clock.to_seconds(t) * 1e6,
+            csper / max_cores as f64,
+            etime,
+            cstime,
+            ttime
+        )
+    }
+
+    /// This method collects the latency percentiles, the per-tenant and per-core statistics and
+    /// the configuration in a machine-readable report.
+    pub fn report(&mut self) -> Report {
+        let cores = self.core_reports();
+        let mut report = Report::new(
+            &self.config,
+            &self.latencies,
+            &self.classes,
+            cores,
+            &self.tenant_stats,
         );
+
+        let (start, end) = self.window();
+        if let Some(dispatcher) = self.dispatcher.as_ref() {
+            let counters = dispatcher.report(self.now);
+            report.dispatcher = Some(match self.dispatcher_baseline.as_ref() {
+                Some(baseline) => counters.since(baseline, start, end),
+                None => counters.since(&counters, start, end),
+            });
+        }
+        report
+    }
+
+    /// This method stops the simulator from printing the results when it is dropped.
+    pub fn set_quiet(&mut self) {
+        self.quiet = true;
     }
 }
 
-impl Drop for Core {
+impl Drop for Simulator {
     fn drop(&mut self) {
-        let preemption_cycles;
-        let cs_cycles;
-        match self.isolation {
-            Isolation::NoIsolation => {
-                preemption_cycles =
-                    self.num_preemptions * consts::NOISOLATION_PREEMPTION_OVERHEAD_CYCLES;
-                cs_cycles = 0;
+        if self.quiet {
+            return;
+        }
+
+        // Calculate & print median & tail latency only on the master thread.
+        let (m, t) = self.latency();
+
+        let mut output = Vec::with_capacity(self.cores.len() + 2);
+        output.push(format!(
+            "Latency: Median(us) {:.2} Tail(us) {:.2}",
+            self.clock.to_seconds(m) * 1e6,
+            self.clock.to_seconds(t) * 1e6,
+        ));
+        let report = self.report();
+        for core in &report.cores {
+            output.push(core.summary(&self.clock));
+        }
+        output.push(format!(
+            "Fairness: Jain {:.4} Short-Tail(us) {:.2} Long-Tail(us) {:.2}",
+            report.fairness, report.short.p99, report.long.p99
+        ));
+        if let Some(dispatcher) = &report.dispatcher {
+            output.push(format!(
+                "Dispatcher: Requests {} Utilization(%) {:.2} Max-Queue {}",
+                dispatcher.requests,
+                dispatcher.utilization * 100.0,
+                dispatcher.max_queue
+            ));
+        }
+
+        for line in &output {
+            println!("{}", line);
+        }
+
+        if let Some(dir) = self.out_dir.clone() {
+            let filename = dir.join("output");
+            if let Err(e) = fs::write(&filename, output.join("\n") + "\n") {
+                error!("Failure writing {}: {}", filename.display(), e);
             }
-            Isolation::PageTableIsolation => {
-                preemption_cycles =
-                    self.num_preemptions * consts::PAGING_PREEMPTION_OVERHEAD_CYCLES;
-                cs_cycles = self.num_context_switches * consts::PAGING_TENANT_SWITCH_CYCLES;
+
+            if let Err(e) = report.write(&dir) {
+                error!("{}", e);
             }
+        }
+    }
+}
+
+impl Core {
+    /// This method computes the CPU cycles spent in tenant switches, preemptions, steals,
+    /// migrations, misses of MPK keys or EPTP list entries, and cache and TLB misses.
+    pub fn overhead_cycles(&self) -> u64 {
+        // Under MPK and VMFUNC the switches out of the domains of the core, and to the stolen
+        // tenants, are full context-switches.
+        let cs_cycles = match self.isolation {
             Isolation::MpkIsolation => {
-                preemption_cycles = self.num_preemptions * consts::MPK_PREEMPTION_OVERHEAD_CYCLES;
-                cs_cycles = self.num_mpk_switches * consts::MPK_TENANT_SWITCH_CYCLES;
+                self.num_mpk_switches * self.switch_cycles
+                    + self.num_context_switches * self.paging_cycles
             }
             Isolation::VmfuncIsolation => {
-                preemption_cycles =
-                    self.num_preemptions * consts::VMFUNC_PREEMPTION_OVERHEAD_CYCLES;
-                cs_cycles = self.num_vmfunc_switches * consts::VMFUNC_TENANT_SWITCH_CYCLES;
+                self.num_vmfunc_switches * self.switch_cycles
+                    + self.num_context_switches * self.paging_cycles
             }
+            _ => self.num_context_switches * self.switch_cycles,
+        };
+        cs_cycles
+            + self.num_preemptions * self.preemption_cycles
+            + self.num_steals * self.steal_cycles
+            + self.num_migrations * self.migration_cycles
+            + self.num_key_misses * self.miss_cycles
+            + self.numa_cycles
+            + self.cold_cycles
+    }
+
+    /// This method collects the counters of this core for the report.
+    pub fn report(&self) -> CoreReport {
+        CoreReport {
+            core_id: self.core_id,
+            core_type: format!("{:?}", self.core_type),
+            numa_node: self
+                .topology
+                .as_ref()
+                .map_or(0, |t| t.node(self.core_id as usize)),
+            requests: self.request_processed,
+            throughput: self.request_processed as f64 / self.clock.to_seconds(self.rdtsc),
+            num_context_switches: self.num_context_switches,
+            num_mpk_switches: self.num_mpk_switches,
+            num_vmfunc_switches: self.num_vmfunc_switches,
+            num_preemptions: self.num_preemptions,
+            num_steals: self.num_steals,
+            num_migrations: self.num_migrations,
+            num_key_misses: self.num_key_misses,
+            num_numa_switches: self.num_numa_switches,
+            cold_cycles: self.cold_cycles,
+            busy_cycles: self.busy_cycles,
+            overhead_cycles: self.overhead_cycles(),
+            total_cycles: self.rdtsc,
         }
+    }
+}
 
-        println!(
-            "Throughput {:.2} Context-Switches(%) {:.2} Execution-Time(sec) {:.2} CS-Time(sec) {:.2} Total-Time(sec) {:.2}",
-            self.request_processed as f64 / cycles::to_seconds(self.rdtsc - 0),
-            (self.num_context_switches as f64 / self.request_processed as f64) * 100.0,
-            self.request_processed as f64/ 1e6,
-            cycles::to_seconds(cs_cycles + preemption_cycles),
-            cycles::to_seconds(self.rdtsc - 0)
-        );
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Arrival;
+    use crate::service::ServiceTime;
+
+    fn config(seed: u64) -> Config {
+        let mut config: Config = toml::from_str(
+            "num_cores = 32
+            large_cores = 0
+            num_tenants = 256
+            tenant_skew = 0.9
+            num_reqs = 2000
+            num_resps = 1000
+            req_rate = 1000000
+            batching = true
+            isolation = \"MpkIsolation\"
+            distribution = \"Zipf\"
+            policy = \"RoundRobin\"",
+        )
+        .unwrap();
+        config.seed = seed;
+        config
+    }
+
+    fn run(seed: u64) -> Histogram {
+        let mut simulator = Simulator::new(config(seed)).unwrap();
+        simulator.set_quiet();
+        simulator.start();
+        simulator.latencies().clone()
+    }
+
+    #[test]
+    fn test_same_seed_same_latencies() {
+        let latencies = run(42);
+        assert!(latencies.count() >= 32 * 1000);
+        assert_eq!(latencies, run(42));
+        assert_ne!(latencies, run(43));
+    }
+
+    #[test]
+    fn test_frequency() {
+        // Each simulator has its own clock; creating a slower one doesn't change the first.
+        let mut simulator = Simulator::new(config(42)).unwrap();
+        let mut slow = config(42);
+        slow.frequency_ghz = 1.5;
+        let mut slow = Simulator::new(slow).unwrap();
+
+        simulator.set_quiet();
+        simulator.start();
+        assert_eq!(simulator.latencies(), &run(42));
+
+        slow.set_quiet();
+        slow.start();
+        assert_ne!(slow.latencies(), simulator.latencies());
+    }
+
+    #[test]
+    fn test_steal() {
+        let mut config = config(42);
+        config.steal = Steal::RequestStealing;
+        config.topology.numa_nodes = 2;
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.set_quiet();
+        simulator.core_init();
+
+        // Eight tenants per core; the first sixteen cores are on the first NUMA node.
+        for (core, count) in &[(1, 1), (2, 2), (20, 1)] {
+            for _i in 0..*count {
+                simulator.cores[*core].add_request(8 * *core as u32 + 1, 0, 1.0);
+            }
+        }
+
+        // An idle core takes one request from the busiest core of its own node, and from
+        // another node only if its own has none.
+        let stolen = |simulator: &Simulator| -> Vec<usize> {
+            simulator.cores.iter().map(|c| c.stealable()).collect()
+        };
+        assert!(simulator.steal_for(0));
+        assert_eq!(stolen(&simulator)[1..3], [1, 1]);
+        assert!(simulator.steal_for(16));
+        assert_eq!(stolen(&simulator)[20], 0);
+        assert!(simulator.steal_for(17));
+        assert!(simulator.steal_for(18));
+        assert!(!simulator.steal_for(19));
+        assert_eq!(simulator.cores[0].num_steals, 1);
+        assert!(!simulator.cores[0].is_idle());
+    }
+
+    #[test]
+    fn test_topology() {
+        let mut config = config(42);
+        config.num_cores = 6;
+        config.num_tenants = 70000;
+        config.req_rate = 200000;
+        config.distribution = Dist::Uniform;
+        config.steal = Steal::RequestStealing;
+        config.arrival = Arrival::Poisson;
+        config.topology.numa_nodes = 2;
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.set_quiet();
+        simulator.start();
+
+        // The idle cores steal on their own NUMA node first; the tenant-ids go past 65535.
+        let report = simulator.report();
+        let nodes: Vec<usize> = report.cores.iter().map(|c| c.numa_node).collect();
+        assert_eq!(nodes, vec![0, 0, 0, 1, 1, 1]);
+        assert!(report.cores.iter().map(|c| c.num_steals).sum::<u64>() > 0);
+        assert!(simulator.latencies().count() >= 6 * 1000);
+        assert!(simulator.tenant_stats[65536..]
+            .iter()
+            .any(|t| t.count() > 0));
+    }
+
+    #[test]
+    fn test_shinjuku() {
+        let mut config = config(42);
+        config.policy = Policy::Shinjuku;
+        config.arrival = Arrival::Poisson;
+        config.req_rate = 100000;
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.set_quiet();
+        simulator.start();
+
+        // Every completed request went through the dispatcher; it doesn't saturate at this rate.
+        let report = simulator.report();
+        let dispatcher = report.dispatcher.unwrap();
+        assert!(simulator.latencies().count() >= 32 * 1000);
+        assert!(dispatcher.requests >= simulator.latencies().count());
+        assert!(dispatcher.utilization > 0.0 && dispatcher.utilization < 1.0);
+    }
+
+    #[test]
+    fn test_no_preemption() {
+        // Without preemption, the quanta of the cores never expires and the requests run to
+        // completion.
+        for preemption in &[true, false] {
+            let mut config = config(42);
+            config.preemption = *preemption;
+            config.costs.quanta_us = 0.5;
+            let mut simulator = Simulator::new(config).unwrap();
+            simulator.set_quiet();
+            simulator.core_init();
+            for core in &simulator.cores {
+                assert_eq!(core.quanta_us.is_finite(), *preemption);
+            }
+
+            let mut req = Request::new(1, 0, 2.0);
+            let quanta_us = simulator.cores[0].quanta_us;
+            let (_, state) = req.run(quanta_us, 0, 0, &simulator.clock);
+            assert_eq!(state == TaskState::Preempted, *preemption);
+        }
+    }
+
+    #[test]
+    fn test_large_cores() {
+        // Enough large cores for the share of the long requests; at least one of each type.
+        assert_eq!(large_cores(32, 0.5), 16);
+        assert_eq!(large_cores(32, 0.1), 4);
+        assert_eq!(large_cores(32, 0.0), 1);
+        assert_eq!(large_cores(32, 1.0), 31);
+        assert_eq!(large_cores(2, 0.9), 1);
+    }
+
+    #[test]
+    fn test_minos_partition() {
+        let mut config = config(42);
+        config.policy = Policy::Minos;
+        config.service_time = ServiceTime::Bimodal {
+            modes_us: vec![1.0, 100.0],
+            weights: vec![99.0, 1.0],
+        };
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.set_quiet();
+        let large = |simulator: &Simulator| -> Vec<usize> {
+            (0..32)
+                .filter(|i| simulator.cores[*i].core_type == CoreType::Large)
+                .collect()
+        };
+
+        // About half of the expected work is long; the last cores start as the large ones.
+        simulator.core_init();
+        assert_eq!(large(&simulator), (15..32).collect::<Vec<usize>>());
+
+        // A tenth of the work which arrived since is long; the large cores shrink to the last
+        // four, with the quanta of their type.
+        simulator.cores[0].arrived_us = 60.0;
+        simulator.cores[1].arrived_us = 40.0;
+        simulator.cores[1].arrived_long_us = 10.0;
+        simulator.partition();
+        assert_eq!(large(&simulator), (28..32).collect::<Vec<usize>>());
+        let costs = &simulator.config.costs;
+        assert_eq!(simulator.cores[27].quanta_us, costs.quanta_us);
+        assert_eq!(simulator.cores[28].quanta_us, costs.large_quanta_us);
+
+        // Without any arrivals, the split stays.
+        simulator.partition();
+        assert_eq!(large(&simulator).len(), 4);
+    }
+
+    #[test]
+    fn test_trace_owner() {
+        let mut config = config(42);
+        config.placement = Placement::Hashed;
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.set_quiet();
+
+        // Each recorded request goes to the core its tenant is placed on, which isn't the one
+        // which would generate its requests.
+        simulator.config.trace.path = String::from("trace.csv");
+        simulator.trace = (1..257)
+            .map(|tenant| TraceRecord {
+                time: tenant,
+                tenant,
+                service_us: 1.0,
+            })
+            .collect();
+        simulator.core_init();
+        let replayed: usize = simulator
+            .cores
+            .iter()
+            .map(|c| c.trace.as_ref().unwrap().len())
+            .sum();
+        assert_eq!(replayed, 256);
+        for core in simulator.cores.iter() {
+            for r in core.trace.as_ref().unwrap() {
+                assert!(core.index.contains_key(&(r.tenant as u32)));
+            }
+        }
+        let moved = |c: &Core| {
+            let range = c.start_tenant as u64..c.end_tenant as u64;
+            c.trace
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|r| !range.contains(&r.tenant))
+        };
+        assert!(simulator.cores.iter().any(moved));
+    }
+
+    #[test]
+    fn test_overhead_cycles() {
+        // With domains of two tenants, some of the switches are full context-switches; the
+        // overhead of the core is all the cycles spent in the switches.
+        for isolation in &[Isolation::MpkIsolation, Isolation::VmfuncIsolation] {
+            let mut config = config(42);
+            config.isolation = isolation.clone();
+            config.mpk.keys = 2;
+            config.vmfunc.eptp_entries = 2;
+            let mut simulator = Simulator::new(config).unwrap();
+            simulator.set_quiet();
+            simulator.core_init();
+
+            let core = &mut simulator.cores[0];
+            let cycles: u64 = [1, 2, 3, 4, 1, 5, 2]
+                .iter()
+                .map(|t| core.tenant_switch(*t, true))
+                .sum();
+            assert!(core.num_context_switches > 0);
+            assert_eq!(core.overhead_cycles(), cycles);
+        }
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let mut config = config(42);
+        config.rebalance.interval_us = 100.0;
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.set_quiet();
+        simulator.core_init();
+
+        // The tenants of the first core have ten times the load of the others; one round moves
+        // four of them to other cores, and every tenant stays on exactly one core.
+        for (t, tenant) in simulator.tenants.iter() {
+            tenant.borrow_mut().demand_us = if *t <= 8 { 10.0 } else { 1.0 };
+        }
+        simulator.rebalance();
+
+        let moved: Vec<u32> = (1..9)
+            .filter(|t| simulator.owner[*t - 1] != 0)
+            .map(|t| t as u32)
+            .collect();
+        assert_eq!(moved, vec![1, 2, 3, 4]);
+        let migrations: u64 = simulator.cores.iter().map(|c| c.num_migrations).sum();
+        assert_eq!(migrations, 4);
+        let placed: usize = simulator.cores.iter().map(|c| c.tenants.len()).sum();
+        assert_eq!(placed, 256);
+        for (t, core) in simulator.owner.iter().enumerate() {
+            assert!(simulator.cores[*core].index.contains_key(&(t as u32 + 1)));
+        }
+        assert!(simulator
+            .tenants
+            .values()
+            .all(|t| t.borrow().demand_us == 0.0));
     }
 }
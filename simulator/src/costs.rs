@@ -0,0 +1,302 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::Isolation;
+use super::consts;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+
+/// The costs of one isolation mechanism.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MechanismCost {
+    // The CPU cycles of a switch between two tenants, including the swap of contexts.
+    pub switch_cycles: u64,
+
+    // The CPU cycles of one preemption, including the signal and the swap of contexts.
+    pub preemption_cycles: u64,
+
+    // If `preemption_cycles` already use an IPI instead of a signal.
+    #[serde(default)]
+    pub ipi: bool,
+}
+
+/// A set of costs; a profile sets only the costs it knows about, and the profiles are applied on
+/// top of each other.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Profile {
+    // The scheduler time quanta on the small cores in micro-seconds.
+    pub quanta_us: Option<f64>,
+
+    // The scheduler time quanta on the large cores in micro-seconds.
+    pub large_quanta_us: Option<f64>,
+
+    // The number of tasks of a tenant run back to back, with batching.
+    pub batch_size: Option<usize>,
+
+    // The CPU cycles to send and receive a signal.
+    pub signal_cycles: Option<u64>,
+
+    // The CPU cycles to send and receive a posted IPI.
+    pub ipi_cycles: Option<u64>,
+
+    // The costs of each isolation mechanism, by name.
+    pub mechanisms: BTreeMap<String, MechanismCost>,
+}
+
+impl Profile {
+    /// This method looks up a built-in profile.
+    ///
+    /// # Arguments
+    /// `name`: The name of the profile; Default, or the source of some costs: Splinter, lmbench,
+    ///         HODOR or Shinjuku.
+    pub fn builtin(name: &str) -> Option<Profile> {
+        let mut profile = Profile::default();
+        let mut mechanism = |isolation: Isolation, switch_cycles, preemption_cycles, ipi| {
+            let cost = MechanismCost {
+                switch_cycles,
+                preemption_cycles,
+                ipi,
+            };
+            profile
+                .mechanisms
+                .insert(isolation.name().to_string(), cost);
+        };
+
+        // The preemption costs of all the mechanisms are from the Shinjuku paper.
+        match name {
+            "Splinter" => mechanism(
+                Isolation::NoIsolation,
+                consts::NOISOLATION_TENANT_SWITCH_CYCLES,
+                consts::NOISOLATION_PREEMPTION_OVERHEAD_CYCLES,
+                false,
+            ),
+            "lmbench" => mechanism(
+                Isolation::PageTableIsolation,
+                consts::PAGING_TENANT_SWITCH_CYCLES,
+                consts::PAGING_PREEMPTION_OVERHEAD_CYCLES,
+                false,
+            ),
+            "HODOR" => mechanism(
+                Isolation::MpkIsolation,
+                consts::MPK_TENANT_SWITCH_CYCLES,
+                consts::MPK_PREEMPTION_OVERHEAD_CYCLES,
+                false,
+            ),
+            "Shinjuku" => {
+                mechanism(
+                    Isolation::VmfuncIsolation,
+                    consts::VMFUNC_TENANT_SWITCH_CYCLES,
+                    consts::VMFUNC_PREEMPTION_OVERHEAD_CYCLES,
+                    true,
+                );
+                profile.signal_cycles = Some(consts::SIGNAL_CYCLES);
+                profile.ipi_cycles = Some(consts::IPI_CYCLES);
+            }
+            "Default" => {
+                for source in &["Splinter", "lmbench", "HODOR", "Shinjuku"] {
+                    profile.apply(&Profile::builtin(source).unwrap());
+                }
+                profile.quanta_us = Some(consts::QUANTA_TIME);
+                profile.large_quanta_us = Some(consts::LARGE_QUNATA_TIME);
+                profile.batch_size = Some(consts::BATCH_SIZE);
+            }
+            _ => return None,
+        }
+        Some(profile)
+    }
+
+    /// This method reads a profile from a TOML file, e.g. one written by the calibration tool.
+    ///
+    /// # Arguments
+    /// `filename`: The path of the profile file.
+    pub fn load(filename: &str) -> Result<Profile, String> {
+        let mut contents = String::new();
+        File::open(filename)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("Failure reading cost profile {}: {}", filename, e))?;
+
+        toml::from_str(&contents)
+            .map_err(|e| format!("Failure parsing cost profile {}: {}", filename, e))
+    }
+
+    /// This method sets the costs which the other profile sets, and keeps the rest.
+    pub fn apply(&mut self, other: &Profile) {
+        self.quanta_us = other.quanta_us.or(self.quanta_us);
+        self.large_quanta_us = other.large_quanta_us.or(self.large_quanta_us);
+        self.batch_size = other.batch_size.or(self.batch_size);
+        self.signal_cycles = other.signal_cycles.or(self.signal_cycles);
+        self.ipi_cycles = other.ipi_cycles.or(self.ipi_cycles);
+        for (name, cost) in &other.mechanisms {
+            self.mechanisms.insert(name.clone(), cost.clone());
+        }
+    }
+}
+
+/// The `[cost_model]` section of the configuration; the profiles to start from, and the costs
+/// which override them.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CostModel {
+    // The built-in profiles or the profile files, applied in order.
+    pub profiles: Vec<String>,
+
+    // The costs set in the configuration, applied after the profiles.
+    #[serde(flatten)]
+    pub overrides: Profile,
+}
+
+impl Default for CostModel {
+    fn default() -> CostModel {
+        CostModel {
+            profiles: vec![String::from("Default")],
+            overrides: Profile::default(),
+        }
+    }
+}
+
+impl CostModel {
+    /// This method applies the profiles and the overrides, in order.
+    ///
+    /// # Return
+    /// The costs, or a message naming the missing profile or cost.
+    pub fn resolve(&self) -> Result<Costs, String> {
+        let mut profile = Profile::default();
+        for name in &self.profiles {
+            match Profile::builtin(name) {
+                Some(builtin) => profile.apply(&builtin),
+                None => profile.apply(&Profile::load(name)?),
+            }
+        }
+        profile.apply(&self.overrides);
+
+        let missing = |name: &str| format!("The cost model doesn't set {}", name);
+        Ok(Costs {
+            quanta_us: profile.quanta_us.ok_or_else(|| missing("quanta_us"))?,
+            large_quanta_us: profile
+                .large_quanta_us
+                .ok_or_else(|| missing("large_quanta_us"))?,
+            batch_size: profile.batch_size.ok_or_else(|| missing("batch_size"))?,
+            signal_cycles: profile
+                .signal_cycles
+                .ok_or_else(|| missing("signal_cycles"))?,
+            ipi_cycles: profile.ipi_cycles.ok_or_else(|| missing("ipi_cycles"))?,
+            mechanisms: profile.mechanisms,
+        })
+    }
+}
+
+/// The costs used by the simulator, once the profiles and the overrides are applied.
+#[derive(Clone, Debug)]
+pub struct Costs {
+    // The scheduler time quanta on the small cores in micro-seconds.
+    pub quanta_us: f64,
+
+    // The scheduler time quanta on the large cores in micro-seconds.
+    pub large_quanta_us: f64,
+
+    // The number of tasks of a tenant run back to back, with batching.
+    pub batch_size: usize,
+
+    // The CPU cycles to send and receive a signal.
+    pub signal_cycles: u64,
+
+    // The CPU cycles to send and receive a posted IPI.
+    pub ipi_cycles: u64,
+
+    // The costs of each isolation mechanism, by name.
+    pub mechanisms: BTreeMap<String, MechanismCost>,
+}
+
+impl Default for Costs {
+    fn default() -> Costs {
+        CostModel::default().resolve().unwrap()
+    }
+}
+
+impl Costs {
+    /// This method returns the costs of an isolation mechanism.
+    pub fn mechanism(&self, isolation: &Isolation) -> Result<&MechanismCost, String> {
+        self.mechanisms
+            .get(isolation.name())
+            .ok_or_else(|| format!("The cost model has no costs for {}", isolation.name()))
+    }
+
+    /// This method returns the CPU cycles of a switch between two tenants.
+    pub fn switch_cycles(&self, isolation: &Isolation) -> u64 {
+        self.mechanism(isolation).unwrap().switch_cycles
+    }
+
+    /// This method returns the CPU cycles spent in one preemption. With an IPI, the signal cost
+    /// is replaced by the IPI cost; a mechanism cheaper than the signal just costs the IPI.
+    ///
+    /// # Arguments
+    /// `isolation`: The isolation mechanism on the core.
+    /// `ipi`: True if a dispatcher core sends an IPI to preempt the task, instead of a signal.
+    pub fn preemption_cycles(&self, isolation: &Isolation, ipi: bool) -> u64 {
+        let cost = self.mechanism(isolation).unwrap();
+        if ipi && !cost.ipi {
+            cost.preemption_cycles.saturating_sub(self.signal_cycles) + self.ipi_cycles
+        } else {
+            cost.preemption_cycles
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        // The default profile has the costs of the built-in mechanisms.
+        let costs = CostModel::default().resolve().unwrap();
+        assert_eq!(costs.switch_cycles(&Isolation::MpkIsolation), 950);
+        assert_eq!(
+            costs.preemption_cycles(&Isolation::PageTableIsolation, true),
+            7800 - 4900 + 2000
+        );
+        assert_eq!(
+            costs.preemption_cycles(&Isolation::VmfuncIsolation, true),
+            3350
+        );
+
+        // The overrides come last, and can add new mechanisms.
+        let model: CostModel = toml::from_str(
+            "quanta_us = 10.0\n\
+             [mechanisms.Cheri]\n\
+             switch_cycles = 300\n\
+             preemption_cycles = 3000",
+        )
+        .unwrap();
+        let costs = model.resolve().unwrap();
+        assert_eq!(costs.quanta_us, 10.0);
+        assert_eq!(costs.batch_size, consts::BATCH_SIZE);
+        let cheri = Isolation::from(String::from("Cheri"));
+        assert_eq!(costs.switch_cycles(&cheri), 300);
+
+        // A preemption cheaper than the signal costs the IPI alone.
+        assert_eq!(costs.preemption_cycles(&cheri, true), costs.ipi_cycles);
+
+        // A single source doesn't set everything.
+        let model = CostModel {
+            profiles: vec![String::from("HODOR")],
+            overrides: Profile::default(),
+        };
+        assert!(model.resolve().is_err());
+    }
+}
@@ -13,56 +13,71 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
-use std::sync::Once;
+/// The clock of the host; the simulated time runs on the virtual clock below, whatever the host.
+pub use common::cycles as host;
 
-static mut CYCLES_PER_SECOND: u64 = 0;
-static INIT: Once = Once::new();
+/// The default frequency of the virtual CPU; 3 GHz.
+pub const DEFAULT_CYCLES_PER_SECOND: u64 = 3_000_000_000;
 
-/// Perform once-only overall initialization for the cycles module, such
-/// as calibrating the clock frequency.  This method is invoked automatically
-/// during initialization.
-/// Stolen from the RAMCloud code base. Thanks, John.
-fn init() -> u64 {
-    let cycles_per_second = 3.0 * 1e9;
-    cycles_per_second as u64
+/// The clock of the virtual CPU, which the simulated time and all the costs are counted in. Each
+/// simulator has its own, from `frequency_ghz` in its configuration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clock {
+    // The number of virtual CPU cycles per second.
+    cycles_per_second: u64,
 }
 
-/// Return the CPU cycles per second for the executing processor.
-///
-/// # Return
-///
-/// Number of CPU cycles per second.
-pub fn cycles_per_second() -> u64 {
-    unsafe {
-        INIT.call_once(|| {
-            CYCLES_PER_SECOND = init();
-        });
-        CYCLES_PER_SECOND
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock::new(DEFAULT_CYCLES_PER_SECOND)
     }
 }
 
-pub fn cycles_per_us() -> f64 {
-    cycles_per_second() as f64 / 1e6
-}
+impl Clock {
+    /// This method creates a virtual clock.
+    ///
+    /// # Arguments
+    /// `cycles_per_second`: The number of virtual CPU cycles per second.
+    pub fn new(cycles_per_second: u64) -> Clock {
+        Clock { cycles_per_second }
+    }
 
-/// Return a 64-bit timestamp using the rdtsc instruction.
-#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-pub fn rdtsc() -> u64 {
-    unsafe {
-        let lo: u32;
-        let hi: u32;
-        llvm_asm!("rdtsc" : "={eax}"(lo), "={edx}"(hi) : : : "volatile");
-        ((hi as u64) << 32) | lo as u64
+    /// This method creates a virtual clock with the given frequency in GHz.
+    pub fn from_ghz(frequency_ghz: f64) -> Clock {
+        Clock::new((frequency_ghz * 1e9) as u64)
+    }
+
+    /// Return the CPU cycles per second of the virtual CPU.
+    ///
+    /// # Return
+    ///
+    /// Number of CPU cycles per second.
+    pub fn cycles_per_second(&self) -> u64 {
+        self.cycles_per_second
+    }
+
+    pub fn cycles_per_us(&self) -> f64 {
+        self.cycles_per_second as f64 / 1e6
+    }
+
+    /// Converts the number of CPU cycles to seconds.
+    ///
+    /// # Arguments
+    /// *`cycles`: Number of CPU cycles.
+    ///
+    /// # Return
+    /// Number of seconds corresponding to the given CPU cycles.
+    pub fn to_seconds(&self, cycles: u64) -> f64 {
+        cycles as f64 / self.cycles_per_second as f64
     }
-}
 
-/// Converts the number of CPU cycles to seconds.
-///
-/// # Arguments
-/// *`cycles`: Number of CPU cycles.
-///
-/// # Return
-/// Number of seconds corresponding to the given CPU cycles.
-pub fn to_seconds(cycles: u64) -> f64 {
-    cycles as f64 / cycles_per_second() as f64
+    /// Converts the number of virtual CPU cycles to nano-seconds.
+    pub fn to_nanos(&self, cycles: u64) -> u64 {
+        (cycles as u128 * 1_000_000_000 / self.cycles_per_second as u128) as u64
+    }
+
+    /// Converts nano-seconds to the number of virtual CPU cycles.
+    pub fn from_nanos(&self, nanos: u64) -> u64 {
+        (nanos as u128 * self.cycles_per_second as u128 / 1_000_000_000) as u64
+    }
 }
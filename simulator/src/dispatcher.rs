@@ -13,12 +13,11 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
+use super::arrival::{self, ArrivalProcess};
 use super::config;
-use super::cycles;
 
 use rand::distributions::{Distribution, Uniform};
-use rand::prelude::*;
-use rand::rngs::ThreadRng;
+use rand::rngs::StdRng;
 use zipf::ZipfDistribution;
 
 pub struct Dispatch {
@@ -28,9 +27,8 @@ pub struct Dispatch {
     // The number of requests generated so far.
     pub sent: u64,
 
-    // The inverse of the rate at which requests are to be generated. Basically, the time interval
-    // between two request generations in cycles.
-    rate_inv: u64,
+    // The arrival process, which decides the time interval between two request generations.
+    arrival: Box<dyn ArrivalProcess>,
 
     // The time stamp at which the next request must be issued in cycles.
     next: u64,
@@ -39,10 +37,10 @@ pub struct Dispatch {
     tenant_rng_zipf: Box<ZipfDistribution>,
 
     // The tenant random number generator.
-    tenant_rng_uniform: Box<Uniform<u16>>,
+    tenant_rng_uniform: Box<Uniform<u32>>,
 
     // Random number generator.
-    rng: Box<ThreadRng>,
+    rng: Box<StdRng>,
 
     // Distribution mechanism amoung tenants on a core.
     distribution: config::Distribution,
@@ -51,38 +49,39 @@ pub struct Dispatch {
 impl Dispatch {
     pub fn new(
         config: &config::Config,
-        low: u16,
-        high: u16,
+        low: u32,
+        high: u32,
         req_rate: u64,
         num_reqs: u64,
+        core_id: u32,
     ) -> Dispatch {
         let num_tenants = (high - low) as usize;
         Dispatch {
             num_requests: num_reqs,
             sent: 0,
-            rate_inv: cycles::cycles_per_second() / req_rate,
+            arrival: arrival::new(config, req_rate, config.rng(core_id, 2)),
             next: 0,
             tenant_rng_zipf: Box::new(
                 ZipfDistribution::new(num_tenants, config.tenant_skew)
                     .expect("Couldn't create tenant RNG."),
             ),
             tenant_rng_uniform: Box::new(Uniform::from(low..high)),
-            rng: Box::new(thread_rng()),
+            rng: Box::new(config.rng(core_id, 0)),
             distribution: config.distribution.clone(),
         }
     }
 
-    pub fn generate_request(&mut self, curr: u64) -> Option<u16> {
+    pub fn generate_request(&mut self, curr: u64) -> Option<u32> {
         if self.sent <= self.num_requests && (curr >= self.next || self.next == 0) {
             self.sent += 1;
-            self.next = 0 + self.sent * self.rate_inv;
+            self.next = self.arrival.next_arrival(self.next);
             match self.distribution {
                 config::Distribution::Uniform => {
                     Some(self.tenant_rng_uniform.sample(&mut *self.rng))
                 }
 
                 config::Distribution::Zipf => {
-                    Some(self.tenant_rng_zipf.sample(&mut *self.rng) as u16)
+                    Some(self.tenant_rng_zipf.sample(&mut *self.rng) as u32)
                 }
             }
         } else {
@@ -90,7 +89,11 @@ impl Dispatch {
         }
     }
 
+    pub fn has_next(&self) -> bool {
+        self.sent <= self.num_requests
+    }
+
     pub fn get_next(&self) -> u64 {
-        self.next.clone()
+        self.next
     }
 }
@@ -0,0 +1,164 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::request::Request;
+use super::sched::{cursor_added, cursor_removed, run_time, take, Context, Scheduler};
+use super::tenant::Tenant;
+
+/// Deficit round robin over the tenants of a core. Each time the core comes to a tenant, the
+/// tenant's deficit grows by its quantum, i.e. the base quantum times its weight, and its requests
+/// run for as long as the deficit covers them. A tenant without requests loses its deficit.
+pub struct Drr {
+    // The index of the tenant whose turn it is.
+    cursor: usize,
+
+    // If the tenant at `cursor` already got its quantum in this turn.
+    topped: bool,
+
+    // The quantum of a tenant of weight one in micro-seconds.
+    quantum_us: f64,
+
+    // The time quanta of the core in micro-seconds; a request runs for at most this long.
+    quanta_us: f64,
+}
+
+impl Drr {
+    pub fn new(quantum_us: f64, quanta_us: f64) -> Drr {
+        Drr {
+            cursor: 0,
+            topped: false,
+            quantum_us,
+            quanta_us,
+        }
+    }
+}
+
+impl Scheduler for Drr {
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        let num_tenants = ctx.tenants.len();
+        if num_tenants == 0 {
+            return None;
+        }
+
+        // The deficits grow on every turn, so the loop ends once a tenant has requests.
+        let mut idle = 0;
+        while idle <= num_tenants {
+            let mut tenant = ctx.tenants[self.cursor].borrow_mut();
+            match tenant.front(0).map(|req| run_time(req, self.quanta_us)) {
+                Some(cost) => {
+                    idle = 0;
+                    if !self.topped {
+                        tenant.credit += self.quantum_us * tenant.weight;
+                        self.topped = true;
+                    }
+                    if cost <= tenant.credit {
+                        tenant.credit -= cost;
+                        return tenant.pop_front(0);
+                    }
+                }
+                None => {
+                    tenant.credit = 0.0;
+                    idle += 1;
+                }
+            }
+            self.cursor = (self.cursor + 1) % num_tenants;
+            self.topped = false;
+        }
+        None
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        // The tenant whose turn comes last.
+        let num_tenants = ctx.tenants.len();
+        for i in 1..num_tenants + 1 {
+            let index = (self.cursor + num_tenants - i) % num_tenants;
+            let stolen = take(ctx, index, 0, all);
+            if !stolen.is_empty() {
+                return stolen;
+            }
+        }
+        Vec::new()
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_removed(&mut self, ctx: &Context, index: usize, _tenant: &Tenant) {
+        if cursor_removed(&mut self.cursor, index, ctx.tenants.len()) {
+            self.topped = false;
+        }
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_added(&mut self, ctx: &Context, index: usize) {
+        ctx.tenants[index].borrow_mut().credit = 0.0;
+        cursor_added(&mut self.cursor, index, ctx.tenants.len());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cores::CoreType;
+    use crate::sched::test::{context, picks, tenants};
+
+    #[test]
+    fn test_drr() {
+        // Each turn, the second tenant runs twice as much as the first; the long request of the
+        // first tenant waits until its deficit covers it.
+        let tenants = tenants(2);
+        tenants[1].borrow_mut().weight = 2.0;
+        let ctx = context(&tenants, CoreType::Small);
+        let mut drr = Drr::new(2.0, 10.0);
+        for (t, time) in &[
+            (1, 1.0),
+            (1, 3.0),
+            (2, 1.0),
+            (2, 1.0),
+            (2, 1.0),
+            (2, 1.0),
+            (2, 1.0),
+        ] {
+            drr.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, *time)));
+        }
+        assert_eq!(
+            picks(&mut drr, &ctx),
+            vec![
+                (1, 1.0),
+                (2, 1.0),
+                (2, 1.0),
+                (2, 1.0),
+                (2, 1.0),
+                (1, 3.0),
+                (2, 1.0)
+            ]
+        );
+
+        // A core without tenants has nothing to run or to steal.
+        let ctx = context(&[], CoreType::Small);
+        assert!(drr.pick_next_task(&ctx).is_none());
+        assert!(drr.steal_task(&ctx, true).is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::cycles::Clock;
+use super::request::Request;
+use super::sched::{min_head, take, Context, Scheduler};
+use super::tenant::Tenant;
+
+/// Earliest deadline first. The deadline of a request is its arrival time plus the SLO of its
+/// tenant; the run-queue of each tenant is ordered by deadline, and the core runs the request with
+/// the earliest deadline of all its tenants.
+pub struct Edf {
+    // The virtual clock, to convert the SLOs to CPU cycles.
+    clock: Clock,
+}
+
+impl Edf {
+    pub fn new(clock: Clock) -> Edf {
+        Edf { clock }
+    }
+
+    // The time-stamp by which the request of the tenant should complete.
+    fn deadline(&self, tenant: &Tenant, req: &Request) -> u64 {
+        req.start_time() + (tenant.slo_us * self.clock.cycles_per_us()) as u64
+    }
+}
+
+impl Scheduler for Edf {
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        let mut tenant = ctx.tenants[index].borrow_mut();
+        let slo = (tenant.slo_us * self.clock.cycles_per_us()) as u64;
+        tenant.insert_by(0, req, |r| r.start_time() + slo);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        self.on_arrival(ctx, index, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        let index = min_head(ctx, 0, |t, r| self.deadline(t, r))?;
+        ctx.tenants[index].borrow_mut().pop_front(0)
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        match min_head(ctx, 0, |t, r| self.deadline(t, r)) {
+            Some(index) => take(ctx, index, 0, all),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cores::CoreType;
+    use crate::sched::test::{context, picks, tenants};
+
+    #[test]
+    fn test_edf() {
+        // The second tenant has a tighter SLO; its requests are due before the earlier ones of
+        // the first tenant.
+        let clock = Clock::default();
+        let us = clock.cycles_per_us() as u64;
+        let tenants = tenants(2);
+        tenants[0].borrow_mut().slo_us = 100.0;
+        tenants[1].borrow_mut().slo_us = 10.0;
+        let ctx = context(&tenants, CoreType::Small);
+        let mut edf = Edf::new(clock);
+        for (t, arrival, time) in &[(1, 0, 1.0), (1, 20, 2.0), (2, 50, 3.0), (2, 100, 4.0)] {
+            let req = Request::new(*t, arrival * us, *time);
+            edf.on_arrival(&ctx, *t as usize - 1, Box::new(req));
+        }
+        assert_eq!(
+            picks(&mut edf, &ctx),
+            vec![(2, 3.0), (1, 1.0), (2, 4.0), (1, 2.0)]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum EventType {
+    // A new request arrives at the core.
+    Arrival,
+
+    // The running task used up its time quanta and must be preempted.
+    QuantumExpiry,
+
+    // The running task finished its execution.
+    Completion,
+
+    // The core finished switching to a new tenant and can start running the task.
+    SwitchDone,
+
+    // The central dispatcher finished handing a request to a worker core.
+    Dispatch,
+
+    // The rebalancer migrates tenants between the cores.
+    Rebalance,
+
+    // Minos decides how many of the cores are large cores.
+    Partition,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Event {
+    // The simulated time-stamp, in CPU cycles, at which this event fires.
+    pub time: u64,
+
+    // The index of the core (in `Simulator.cores`) this event belongs to; one past the last core
+    // for the central dispatcher, the rebalancer and the Minos partitioning.
+    pub core: usize,
+
+    // The type of the event.
+    pub event_type: EventType,
+
+    // Insertion order; breaks ties between events which fire at the same time-stamp.
+    seq: u64,
+}
+
+impl Event {
+    pub fn new(time: u64, core: usize, event_type: EventType) -> Event {
+        Event {
+            time,
+            core,
+            event_type,
+            seq: 0,
+        }
+    }
+}
+
+// The events are ordered, and equal, by their time-stamp and insertion order; the pushed events
+// are never equal.
+impl PartialEq for Event {
+    fn eq(&self, other: &Event) -> bool {
+        (self.time, self.seq) == (other.time, other.seq)
+    }
+}
+
+impl Eq for Event {}
+
+impl Ord for Event {
+    fn cmp(&self, other: &Event) -> Ordering {
+        // BinaryHeap is a max-heap; reverse the order to pop the earliest event first.
+        other
+            .time
+            .cmp(&self.time)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Event) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The global event queue, which orders the events of all the cores on one shared clock.
+pub struct EventQueue {
+    // Min-heap of the pending events.
+    events: BinaryHeap<Event>,
+
+    // The number of events pushed so far.
+    seq: u64,
+}
+
+impl Default for EventQueue {
+    fn default() -> EventQueue {
+        EventQueue::new()
+    }
+}
+
+impl EventQueue {
+    pub fn new() -> EventQueue {
+        EventQueue {
+            events: BinaryHeap::with_capacity(1024),
+            seq: 0,
+        }
+    }
+
+    /// This method adds a new event to the queue.
+    ///
+    /// # Arguments
+    /// `event`: The event to schedule.
+    pub fn push(&mut self, mut event: Event) {
+        event.seq = self.seq;
+        self.seq += 1;
+        self.events.push(event);
+    }
+
+    /// This method removes the earliest event from the queue.
+    ///
+    /// # Return
+    /// The event with the smallest time-stamp; events with the same time-stamp are returned in
+    /// the order in which they were pushed.
+    pub fn pop(&mut self) -> Option<Event> {
+        self.events.pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_event_order() {
+        let mut queue = EventQueue::new();
+        queue.push(Event::new(30, 0, EventType::Completion));
+        queue.push(Event::new(10, 1, EventType::Arrival));
+        queue.push(Event::new(10, 0, EventType::SwitchDone));
+        queue.push(Event::new(20, 2, EventType::QuantumExpiry));
+
+        let order: Vec<(u64, usize)> = (0..4)
+            .map(|_| queue.pop().unwrap())
+            .map(|e| (e.time, e.core))
+            .collect();
+        assert_eq!(order, vec![(10, 1), (10, 0), (20, 2), (30, 0)]);
+        assert!(queue.is_empty());
+
+        // Equality agrees with the order.
+        let a = Event::new(10, 0, EventType::Arrival);
+        let b = Event::new(10, 1, EventType::Completion);
+        assert_eq!(a == b, a.cmp(&b) == Ordering::Equal);
+    }
+}
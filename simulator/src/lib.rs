@@ -13,10 +13,12 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
-#![feature(llvm_asm, integer_atomics)]
-
+extern crate common;
+extern crate core_affinity;
+extern crate libc;
 extern crate serde;
 extern crate serde_aux;
+extern crate serde_json;
 #[macro_use]
 extern crate serde_derive;
 extern crate toml;
@@ -30,6 +32,13 @@ pub mod config;
 /// This module contains the various constants which are used throughout the simulation.
 pub mod consts;
 
+/// This module contains the cost model; the switch and preemption costs of each isolation
+/// mechanism, and the built-in profiles.
+pub mod costs;
+
+/// This module measures the switch and preemption costs on the host, for a cost profile.
+pub mod calibrate;
+
 /// This module simulate the functionality of a CPU core.
 pub mod cores;
 
@@ -42,12 +51,54 @@ pub mod request;
 /// This module is used to generate the requests for given number of tenants.
 pub mod dispatcher;
 
+/// This module contains the arrival processes which decide when the requests are generated.
+pub mod arrival;
+
+/// This module contains the service time distributions of the requests.
+pub mod service;
+
+/// This module reads the request traces which can be replayed instead of generating requests.
+pub mod trace;
+
+/// This module contains the global event queue which drives the simulation.
+pub mod event;
+
 /// This module contains the CPU cycles related functionality; rdtsc() etc.
 pub mod cycles;
 
-/// This module contains a trait to implement the scheduler.
+/// This module contains the machine-readable results of a simulator run.
+pub mod report;
+
+/// This module runs the simulator over a grid of configurations and writes the result tables.
+pub mod sweep;
+
+/// This module contains the trait to implement the scheduler of a core.
 pub mod sched;
 
 // Different scheduling techniques.
+mod drr_sched;
+mod edf_sched;
+mod lottery_sched;
 mod minos_sched;
+mod priority_sched;
+mod ps_sched;
 mod rr_sched;
+mod srpt_sched;
+mod stride_sched;
+mod wfq_sched;
+
+/// This module contains the central dispatcher of the Shinjuku policy.
+pub mod shinjuku;
+
+/// This module models the cache and TLB state which the tenants of a core evict from each other.
+pub mod cache;
+
+/// This module models the MPK protection keys shared by the tenants of an address space, and the
+/// EPTP list entries shared the same way with VMFUNC.
+pub mod mpk;
+
+/// This module decides which core runs each tenant, and plans the migrations of the rebalancer.
+pub mod placement;
+
+/// This module describes the sockets, NUMA nodes and SMT siblings of the simulated cores.
+pub mod topology;
@@ -0,0 +1,125 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::request::Request;
+use super::sched::{take, Context, Scheduler};
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+/// Lottery scheduling over the tenants of a core. Each tenant with runnable requests holds as
+/// many tickets as its weight, and the tenant of the next request is drawn at random; a tenant
+/// gets the core in proportion to its weight, on average.
+pub struct Lottery {
+    // The random number generator for the draws.
+    rng: StdRng,
+}
+
+impl Lottery {
+    pub fn new(rng: StdRng) -> Lottery {
+        Lottery { rng }
+    }
+
+    // Draw a tenant with runnable requests; None if there is none.
+    fn draw(&mut self, ctx: &Context) -> Option<usize> {
+        let tickets = |index: usize| {
+            let tenant = ctx.tenants[index].borrow();
+            match tenant.len(0) {
+                0 => 0.0,
+                _ => tenant.weight,
+            }
+        };
+        let total: f64 = (0..ctx.tenants.len()).map(tickets).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut u = self.rng.gen::<f64>() * total;
+        let mut winner = None;
+        for index in 0..ctx.tenants.len() {
+            let t = tickets(index);
+            if t > 0.0 {
+                winner = Some(index);
+                if u < t {
+                    break;
+                }
+                u -= t;
+            }
+        }
+        winner
+    }
+}
+
+impl Scheduler for Lottery {
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_front(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        let index = self.draw(ctx)?;
+        ctx.tenants[index].borrow_mut().pop_front(0)
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        match self.draw(ctx) {
+            Some(index) => take(ctx, index, 0, all),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cores::CoreType;
+    use crate::sched::test::{context, tenants};
+
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_lottery() {
+        // Both tenants stay backlogged; the second one wins three times as many draws.
+        let tenants = tenants(2);
+        tenants[1].borrow_mut().weight = 3.0;
+        let ctx = context(&tenants, CoreType::Small);
+        let mut lottery = Lottery::new(StdRng::seed_from_u64(7));
+        for t in &[1, 2] {
+            for _i in 0..4000 {
+                lottery.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, 1.0)));
+            }
+        }
+        let mut wins = [0, 0];
+        for _i in 0..4000 {
+            let req = lottery.pick_next_task(&ctx).unwrap();
+            wins[req.get_tenant() as usize - 1] += 1;
+        }
+        let ratio = wins[1] as f64 / wins[0] as f64;
+        assert!((ratio - 3.0).abs() < 0.3, "{}", ratio);
+
+        // The tenants without requests hold no tickets.
+        for _i in 0..4000 {
+            assert!(lottery.pick_next_task(&ctx).is_some());
+        }
+        assert!(lottery.pick_next_task(&ctx).is_none());
+    }
+}
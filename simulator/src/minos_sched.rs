@@ -13,58 +13,116 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
-use super::consts;
 use super::cores::CoreType;
 use super::request::Request;
-use super::sched::Scheduler;
+use super::rr_sched::RoundRobin;
+use super::sched::{Context, Scheduler};
+use super::tenant::Tenant;
 
-use std::collections::VecDeque;
+// The run-queues of each tenant; the short requests run on the small cores and the long ones on
+// the large cores.
+const SMALL_RQ: usize = 0;
+const LARGE_RQ: usize = 1;
 
+/// The Minos scheduler; each tenant has a run-queue for the short and one for the long requests.
+/// A core goes through its tenants like `RoundRobin`, over the run-queue of its core type; the
+/// requests of the other run-queue wait for a core of the other type to take them.
 pub struct Minos {
-    // Task runqueue for small requests.
-    pub small_rq: VecDeque<Box<Request>>,
+    // The round-robin walk over the tenants of the core.
+    rr: RoundRobin,
 
-    // Task runqueue for large request.
-    pub large_rq: VecDeque<Box<Request>>,
+    // Tasks up to this long, in micro-seconds, go to the small runqueue.
+    threshold: f64,
+
+    // The number of requests in each run-queue of all the tenants of the core.
+    queued: [usize; 2],
 }
 
 impl Minos {
-    pub fn new() -> Minos {
+    pub fn new(batch_size: usize, threshold: f64) -> Minos {
         Minos {
-            small_rq: VecDeque::with_capacity(32),
-            large_rq: VecDeque::with_capacity(32),
+            rr: RoundRobin::new(batch_size),
+            threshold,
+            queued: [0; 2],
+        }
+    }
+
+    // The run-queue which the cores of the given type run; the other one is left to the cores of
+    // the other type.
+    fn queue(core_type: CoreType) -> usize {
+        match core_type {
+            CoreType::Small => SMALL_RQ,
+            CoreType::Large => LARGE_RQ,
+        }
+    }
+
+    // The run-queue which the cores of the given type don't run.
+    fn foreign(core_type: CoreType) -> usize {
+        match core_type {
+            CoreType::Small => LARGE_RQ,
+            CoreType::Large => SMALL_RQ,
         }
     }
+
+    // Count the requests taken from or added to the given run-queue.
+    fn count(&mut self, queue: usize, taken: usize, added: usize) {
+        self.queued[queue] = self.queued[queue] + added - taken;
+    }
 }
 
 impl Scheduler for Minos {
     // Lookup the `Scheduler` trait for documentation on this method.
-    fn create_task(&mut self, rdtsc: u64, task_time: f64, tenant_id: u16) {
-        let req = Box::new(Request::new(tenant_id, rdtsc, task_time));
-        if task_time == consts::TASK_DISTRIBUTION_TIME[0] {
-            self.small_rq.push_back(req);
-        } else {
-            self.large_rq.push_back(req);
-        }
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        let mut tenant = ctx.tenants[index].borrow_mut();
+        let queue = match req.max_time() <= self.threshold {
+            true => SMALL_RQ,
+            false => LARGE_RQ,
+        };
+        tenant.push_back(queue, req);
+        self.count(queue, 0, 1);
     }
 
     // Lookup the `Scheduler` trait for documentation on this method.
-    fn pick_next_task(&mut self, coretype: CoreType) -> Option<Box<Request>> {
-        match coretype {
-            CoreType::Small => {
-                // Don't allow large tasks on small cores due to head of line blocking.
-                self.small_rq.pop_front()
-            }
-
-            CoreType::Large => {
-                // Don't allow small tasks as the rdtsc is out of sync on small and large cores.
-                self.large_rq.pop_front()
-            }
-        }
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_front(LARGE_RQ, req);
+        self.count(LARGE_RQ, 0, 1);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        // Don't allow large tasks on small cores due to head of line blocking.
+        let queue = Minos::queue(ctx.core_type);
+        let task = self.rr.pick_from(ctx, queue);
+        self.count(queue, task.is_some() as usize, 0);
+        task
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        // Only the requests which this core doesn't run move, to a core of the other type.
+        let queue = Minos::foreign(ctx.core_type);
+        let stolen = self.rr.steal_from(ctx, queue, all);
+        self.count(queue, stolen.len(), 0);
+        stolen
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn stealable(&self, ctx: &Context) -> Option<usize> {
+        Some(self.queued[Minos::foreign(ctx.core_type)])
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_removed(&mut self, ctx: &Context, index: usize, tenant: &Tenant) {
+        self.count(SMALL_RQ, tenant.len(SMALL_RQ), 0);
+        self.count(LARGE_RQ, tenant.len(LARGE_RQ), 0);
+        self.rr.on_tenant_removed(ctx, index, tenant);
     }
 
     // Lookup the `Scheduler` trait for documentation on this method.
-    fn enqueue_task(&mut self, req: Box<Request>) {
-        self.large_rq.push_front(req);
+    fn on_tenant_added(&mut self, ctx: &Context, index: usize) {
+        let tenant = ctx.tenants[index].borrow();
+        self.count(SMALL_RQ, 0, tenant.len(SMALL_RQ));
+        self.count(LARGE_RQ, 0, tenant.len(LARGE_RQ));
+        self.rr.on_tenant_added(ctx, index);
     }
 }
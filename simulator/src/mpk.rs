@@ -0,0 +1,143 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::KeyPolicy;
+
+use std::collections::HashMap;
+
+/// The protection keys of one address space, virtualized over more tenants than keys (like
+/// libmpk). A tenant runs only while it holds a key; otherwise a key is taken from another
+/// tenant, and the pages of both tenants are re-tagged. The entries of a VMFUNC EPTP list are
+/// shared the same way.
+pub struct KeyCache {
+    // The tenant holding each key; None if the key is free.
+    holders: Vec<Option<u32>>,
+
+    // The key held by each tenant.
+    held: HashMap<u32, usize>,
+
+    // The time, in accesses, at which each key was last used.
+    last_use: Vec<u64>,
+
+    // The number of accesses to each key since it was assigned to its tenant.
+    uses: Vec<u64>,
+
+    // The number of accesses so far.
+    clock: u64,
+
+    // This is to decide which key to take when all of them are in use; Lru or Lfu.
+    policy: KeyPolicy,
+}
+
+impl KeyCache {
+    /// This method creates a cache with all the keys free.
+    ///
+    /// # Arguments
+    /// `keys`: The number of keys for the tenants.
+    /// `policy`: The eviction policy; Lru or Lfu.
+    pub fn new(keys: usize, policy: KeyPolicy) -> KeyCache {
+        KeyCache {
+            holders: vec![None; keys],
+            held: HashMap::new(),
+            last_use: vec![0; keys],
+            uses: vec![0; keys],
+            clock: 0,
+            policy,
+        }
+    }
+
+    /// This method gives the tenant a key, for it to run.
+    ///
+    /// # Return
+    /// True if the tenant already held a key; false on a key miss, which re-tags pages.
+    pub fn access(&mut self, tenant: u32) -> bool {
+        self.clock += 1;
+        let (key, hit) = match self.held.get(&tenant) {
+            Some(key) => (*key, true),
+            None => (self.victim(), false),
+        };
+
+        if !hit {
+            if let Some(evicted) = self.holders[key] {
+                self.held.remove(&evicted);
+            }
+            self.holders[key] = Some(tenant);
+            self.held.insert(tenant, key);
+            self.uses[key] = 0;
+        }
+        self.last_use[key] = self.clock;
+        self.uses[key] += 1;
+        hit
+    }
+
+    /// This method frees the key of a tenant, e.g. when it moves to another core.
+    pub fn forget(&mut self, tenant: u32) {
+        if let Some(key) = self.held.remove(&tenant) {
+            self.holders[key] = None;
+        }
+    }
+
+    // The key to assign to a new tenant; a free one, or the one picked by the policy. Ties go
+    // to the least recently used key.
+    fn victim(&self) -> usize {
+        if let Some(key) = self.holders.iter().position(|h| h.is_none()) {
+            return key;
+        }
+
+        let mut victim = 0;
+        for key in 1..self.holders.len() {
+            let better = match self.policy {
+                KeyPolicy::Lfu => {
+                    self.uses[key] < self.uses[victim]
+                        || (self.uses[key] == self.uses[victim]
+                            && self.last_use[key] < self.last_use[victim])
+                }
+                _ => self.last_use[key] < self.last_use[victim],
+            };
+            if better {
+                victim = key;
+            }
+        }
+        victim
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_eviction() {
+        // Tenant 1 is used often but not recently; LRU evicts it, LFU keeps it.
+        let run = |policy| {
+            let mut keys = KeyCache::new(2, policy);
+            for _ in 0..3 {
+                keys.access(1);
+            }
+            keys.access(2);
+            keys.access(3);
+            keys.access(1)
+        };
+        assert!(!run(KeyPolicy::Lru));
+        assert!(run(KeyPolicy::Lfu));
+
+        // A forgotten tenant misses, and frees its key for the next one.
+        let mut keys = KeyCache::new(1, KeyPolicy::Lru);
+        assert!(!keys.access(1));
+        assert!(keys.access(1));
+        keys.forget(1);
+        assert!(!keys.access(1));
+    }
+}
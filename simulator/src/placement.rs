@@ -0,0 +1,306 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::{Config, Distribution, Isolation, KeyPolicy, Placement};
+use super::service::ServiceModel;
+
+use std::cmp::min;
+use std::ops::Range;
+
+/// This function returns the contiguous range of tenant-ids of a core; the last core also takes
+/// the remainder. Each core generates the requests of its range, whatever the placement.
+///
+/// # Arguments
+/// `num_tenants`: The total number of tenants.
+/// `num_cores`: The number of cores the tenants are split over.
+/// `core`: The core, between zero and `num_cores - 1`.
+pub fn contiguous(num_tenants: u64, num_cores: u64, core: u64) -> Range<u32> {
+    let uniform_divide = num_tenants as u32 / num_cores as u32;
+    let low = (core as u32 * uniform_divide) + 1;
+    let mut high = low + uniform_divide;
+    if core == num_cores - 1 {
+        high = num_tenants as u32 + 1;
+    }
+    low..high
+}
+
+/// The number of tenants which share one isolation domain on a core; one without domains. The
+/// virtualized MPK keys and the EPTP list cache are shared by all the tenants of a core,
+/// whichever they are, so they don't make a domain to place together.
+pub fn domain_size(config: &Config) -> usize {
+    match config.isolation {
+        Isolation::MpkIsolation if config.mpk.policy == KeyPolicy::Static => {
+            config.mpk.keys as usize
+        }
+        Isolation::MpkIsolation => 1,
+        Isolation::VmfuncIsolation if config.vmfunc.policy == KeyPolicy::Static => {
+            config.vmfunc.eptp_entries as usize
+        }
+        Isolation::VmfuncIsolation => 1,
+        _ => 1,
+    }
+}
+
+/// This function computes the expected load of each tenant from the configuration; the request
+/// rate of the tenant times its mean service time.
+///
+/// # Return
+/// The load of each tenant, in micro-seconds of service per second, indexed by tenant-id - 1.
+pub fn expected_load(config: &Config) -> Vec<f64> {
+    let service = ServiceModel::new(config);
+    let mut load = vec![0.0; config.num_tenants as usize];
+    for core in 0..config.num_cores {
+        let range = contiguous(config.num_tenants, config.num_cores, core);
+        let n = range.len();
+
+        // The share of the requests of the core which go to each of its tenants.
+        let weights: Vec<f64> = match config.distribution {
+            Distribution::Uniform => vec![1.0; n],
+            Distribution::Zipf => (1..n + 1)
+                .map(|k| 1.0 / (k as f64).powf(config.tenant_skew))
+                .collect(),
+        };
+        let total: f64 = weights.iter().sum();
+
+        for (tenant, weight) in range.zip(weights) {
+            // An infinite mean would hide the differences between the other tenants.
+            let mut mean = service.mean(tenant);
+            if !mean.is_finite() {
+                mean = 1.0;
+            }
+            load[tenant as usize - 1] = config.req_rate as f64 * weight / total * mean;
+        }
+    }
+    load
+}
+
+/// This function decides which small core runs each tenant.
+///
+/// # Return
+/// The index of the core of each tenant, indexed by tenant-id - 1.
+pub fn place(config: &Config) -> Vec<usize> {
+    let num_tenants = config.num_tenants as usize;
+    let num_cores = config.num_cores as usize;
+    match config.placement {
+        Placement::Contiguous => {
+            let mut owner = vec![0; num_tenants];
+            for core in 0..config.num_cores {
+                for tenant in contiguous(config.num_tenants, config.num_cores, core) {
+                    owner[tenant as usize - 1] = core as usize;
+                }
+            }
+            owner
+        }
+
+        Placement::Hashed => (1..num_tenants as u64 + 1)
+            .map(|t| (t.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) as usize % num_cores)
+            .collect(),
+
+        Placement::LoadAware => least_loaded(&expected_load(config), 1, num_cores),
+
+        // A domain larger than the share of a core is split, so that every core gets tenants.
+        Placement::DomainAware => {
+            let share = num_tenants.div_ceil(num_cores);
+            let size = min(domain_size(config), share);
+            least_loaded(&expected_load(config), size, num_cores)
+        }
+    }
+}
+
+// Split the tenants in groups of `size` consecutive tenant-ids, and assign the groups, heaviest
+// first, to the core with the least load so far.
+fn least_loaded(load: &[f64], size: usize, num_cores: usize) -> Vec<usize> {
+    let mut groups: Vec<(usize, f64)> = load
+        .chunks(size)
+        .enumerate()
+        .map(|(i, chunk)| (i, chunk.iter().sum()))
+        .collect();
+    groups.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+
+    let mut core_load = vec![0.0; num_cores];
+    let mut owner = vec![0; load.len()];
+    for (group, group_load) in groups {
+        let core = lightest(&core_load);
+        core_load[core] += group_load;
+        owner[group * size..min((group + 1) * size, load.len())].fill(core);
+    }
+    owner
+}
+
+// The index of the core with the least load; the lowest index on a tie.
+fn lightest(core_load: &[f64]) -> usize {
+    let mut best = 0;
+    for (i, load) in core_load.iter().enumerate() {
+        if *load < core_load[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+// The index of the core with the most load; the lowest index on a tie.
+fn heaviest(core_load: &[f64]) -> usize {
+    let mut best = 0;
+    for (i, load) in core_load.iter().enumerate() {
+        if *load > core_load[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+/// This function plans one rebalancing round. It repeatedly moves one tenant from the most to
+/// the least loaded core; the one whose load is closest to half the gap between the two.
+///
+/// # Arguments
+/// `load`: The observed load of each tenant, indexed by tenant-id - 1.
+/// `owner`: The core of each tenant, indexed by tenant-id - 1.
+/// `pinned`: The tenants which can't move now, indexed by tenant-id - 1.
+/// `num_cores`: The number of cores.
+/// `threshold`: Stop once the most loaded core is within this fraction above the mean load.
+/// `max_migrations`: The maximum number of tenants to move.
+///
+/// # Return
+/// The tenant-id and the new core of each tenant to move, in order.
+pub fn plan(
+    load: &[f64],
+    owner: &[usize],
+    pinned: &[bool],
+    num_cores: usize,
+    threshold: f64,
+    max_migrations: u64,
+) -> Vec<(u32, usize)> {
+    let mut owner = owner.to_vec();
+    let mut pinned = pinned.to_vec();
+    let mut core_load = vec![0.0; num_cores];
+    for (tenant, core) in owner.iter().enumerate() {
+        core_load[*core] += load[tenant];
+    }
+    let mean = core_load.iter().sum::<f64>() / num_cores as f64;
+
+    let mut moves = Vec::new();
+    while (moves.len() as u64) < max_migrations {
+        let (src, dst) = (heaviest(&core_load), lightest(&core_load));
+        if core_load[src] <= mean * (1.0 + threshold) {
+            break;
+        }
+
+        // Moving a tenant lighter than the gap narrows it; half the gap evens the two cores.
+        let gap = core_load[src] - core_load[dst];
+        let mut best: Option<usize> = None;
+        for tenant in 0..load.len() {
+            if owner[tenant] != src || pinned[tenant] || load[tenant] <= 0.0 {
+                continue;
+            }
+            if load[tenant] >= gap {
+                continue;
+            }
+            let distance = (load[tenant] - gap / 2.0).abs();
+            if best.is_none_or(|b| distance < (load[b] - gap / 2.0).abs()) {
+                best = Some(tenant);
+            }
+        }
+
+        let tenant = match best {
+            Some(tenant) => tenant,
+            None => break,
+        };
+        owner[tenant] = dst;
+        pinned[tenant] = true;
+        core_load[src] -= load[tenant];
+        core_load[dst] += load[tenant];
+        moves.push((tenant as u32 + 1, dst));
+    }
+    moves
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config(isolation: Isolation) -> Config {
+        let mut config: Config = toml::from_str(
+            "num_cores = 8
+            large_cores = 0
+            num_tenants = 1000
+            tenant_skew = 0.9
+            num_reqs = 2000
+            num_resps = 1000
+            req_rate = 1000000
+            batching = true
+            isolation = \"NoIsolation\"
+            distribution = \"Zipf\"
+            policy = \"RoundRobin\"
+            placement = \"DomainAware\"",
+        )
+        .unwrap();
+        config.isolation = isolation;
+        config
+    }
+
+    #[test]
+    fn test_domain_aware() {
+        // The 512 tenants of an EPTP list are more than the share of a core; every core gets
+        // tenants. The virtualized MPK keys and EPTP entries don't group the tenants.
+        let mut lru = config(Isolation::MpkIsolation);
+        lru.mpk.policy = KeyPolicy::Lru;
+        assert_eq!(domain_size(&lru), 1);
+        let mut lfu = config(Isolation::VmfuncIsolation);
+        lfu.vmfunc.policy = KeyPolicy::Lfu;
+        assert_eq!(domain_size(&lfu), 1);
+        let isolations = vec![
+            Isolation::VmfuncIsolation,
+            Isolation::MpkIsolation,
+            Isolation::PageTableIsolation,
+        ];
+        for isolation in isolations {
+            let owner = place(&config(isolation.clone()));
+            for core in 0..8 {
+                assert!(owner.contains(&core), "{:?} {}", isolation, core);
+            }
+        }
+    }
+
+    #[test]
+    fn test_least_loaded() {
+        // The two heavy tenants end up on different cores, and the light ones fill in.
+        let owner = least_loaded(&[8.0, 1.0, 8.0, 1.0, 1.0, 1.0], 1, 2);
+        assert_ne!(owner[0], owner[2]);
+        let on_first = owner.iter().filter(|c| **c == owner[0]).count();
+        assert_eq!(on_first, 3);
+
+        // Groups of two consecutive tenants stay together.
+        let owner = least_loaded(&[4.0, 4.0, 1.0, 1.0, 3.0], 2, 2);
+        assert_eq!(owner[0], owner[1]);
+        assert_eq!(owner[2], owner[3]);
+        assert_ne!(owner[0], owner[2]);
+    }
+
+    #[test]
+    fn test_plan() {
+        // Core 0 has 10, core 1 has 2; moving the tenant with 4 evens them out.
+        let load = [5.0, 4.0, 1.0, 2.0];
+        let owner = [0, 0, 0, 1];
+        let moves = plan(&load, &owner, &[false; 4], 2, 0.1, 4);
+        assert_eq!(moves, vec![(2, 1)]);
+
+        // A pinned tenant stays; the next best one moves instead.
+        let moves = plan(&load, &owner, &[false, true, false, false], 2, 0.1, 4);
+        assert_eq!(moves[0], (1, 1));
+
+        // Nothing moves when the load is within the threshold; 7 is less than 20% over 6.
+        assert!(plan(&load, &[0, 1, 1, 1], &[false; 4], 2, 0.2, 4).is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::request::Request;
+use super::rr_sched::RoundRobin;
+use super::sched::{Context, Scheduler};
+use super::tenant::Tenant;
+
+/// Strict priority classes. The core runs the tenants of the highest class with runnable
+/// requests, in the round-robin order; the lower classes wait until it has none.
+pub struct Priority {
+    // The round-robin walk over the tenants of the core.
+    rr: RoundRobin,
+}
+
+impl Priority {
+    pub fn new(batch_size: usize) -> Priority {
+        Priority {
+            rr: RoundRobin::new(batch_size),
+        }
+    }
+}
+
+// The highest priority class with runnable requests; None if there are no requests.
+fn top_class(ctx: &Context) -> Option<u32> {
+    ctx.tenants
+        .iter()
+        .map(|t| t.borrow())
+        .filter(|t| t.len(0) > 0)
+        .map(|t| t.priority)
+        .min()
+}
+
+impl Scheduler for Priority {
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        let top = top_class(ctx)?;
+        self.rr.pick_where(ctx, 0, |t| t.priority == top)
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        match top_class(ctx) {
+            Some(top) => self.rr.steal_where(ctx, 0, all, |t| t.priority == top),
+            None => Vec::new(),
+        }
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_removed(&mut self, ctx: &Context, index: usize, tenant: &Tenant) {
+        self.rr.on_tenant_removed(ctx, index, tenant);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_added(&mut self, ctx: &Context, index: usize) {
+        self.rr.on_tenant_added(ctx, index);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cores::CoreType;
+    use crate::sched::test::{context, picks, tenants};
+
+    #[test]
+    fn test_priority() {
+        // The tenants of class zero take turns; the first tenant only runs once they are done,
+        // and it is the last one a thief takes from.
+        let tenants = tenants(3);
+        tenants[0].borrow_mut().priority = 1;
+        let ctx = context(&tenants, CoreType::Small);
+        let mut priority = Priority::new(1);
+        for t in &[1, 1, 2, 2, 3] {
+            let req = Box::new(Request::new(*t, 0, *t as f64));
+            priority.on_arrival(&ctx, *t as usize - 1, req);
+        }
+        assert_eq!(priority.steal_task(&ctx, false)[0].get_tenant(), 2);
+        assert_eq!(
+            picks(&mut priority, &ctx),
+            vec![(2, 2.0), (3, 3.0), (1, 1.0), (1, 1.0)]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::request::Request;
+use super::sched::{take, Context, Scheduler};
+use super::tenant::Tenant;
+
+use std::collections::VecDeque;
+
+/// Processor sharing; all the runnable requests of a core take turns, whatever their tenant,
+/// and each turn lasts one tiny time quanta. The core makes the switches and the preemptions
+/// free, so the requests share the core evenly as in the theoretical model.
+pub struct ProcessorSharing {
+    // The tenant-id of each runnable request, in the order of their turns.
+    turns: VecDeque<u32>,
+}
+
+impl ProcessorSharing {
+    pub fn new() -> ProcessorSharing {
+        ProcessorSharing {
+            turns: VecDeque::with_capacity(1024),
+        }
+    }
+
+    // Drop the turns of `count` requests of the tenant; the last ones.
+    fn forget(&mut self, tenant: u32, count: usize) {
+        for _i in 0..count {
+            match self.turns.iter().rposition(|t| *t == tenant) {
+                Some(position) => {
+                    self.turns.remove(position);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+// The index of the tenant in the tenants of the core, which are ordered by tenant-id.
+fn index_of(ctx: &Context, tenant: u32) -> Option<usize> {
+    ctx.tenants
+        .binary_search_by_key(&tenant, |t| t.borrow().tenant_id)
+        .ok()
+}
+
+impl Scheduler for ProcessorSharing {
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        self.turns.push_back(req.get_tenant());
+        ctx.tenants[index].borrow_mut().push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        self.on_arrival(ctx, index, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        while let Some(tenant) = self.turns.pop_front() {
+            if let Some(index) = index_of(ctx, tenant) {
+                if let Some(req) = ctx.tenants[index].borrow_mut().pop_front(0) {
+                    return Some(req);
+                }
+            }
+        }
+        None
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        let tenant = match self.turns.front() {
+            Some(tenant) => *tenant,
+            None => return Vec::new(),
+        };
+        let stolen = match index_of(ctx, tenant) {
+            Some(index) => take(ctx, index, 0, all),
+            None => Vec::new(),
+        };
+        self.forget(tenant, stolen.len());
+        stolen
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_removed(&mut self, _ctx: &Context, _index: usize, tenant: &Tenant) {
+        let id = tenant.tenant_id;
+        self.turns.retain(|t| *t != id);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_added(&mut self, ctx: &Context, index: usize) {
+        let tenant = ctx.tenants[index].borrow();
+        for _i in 0..tenant.queued() {
+            self.turns.push_back(tenant.tenant_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cores::CoreType;
+    use crate::sched::test::{context, picks, tenants};
+
+    #[test]
+    fn test_processor_sharing() {
+        // The requests take turns in the order they arrived, whatever their tenant; a preempted
+        // request waits for the turns of all the others.
+        let tenants = tenants(2);
+        let ctx = context(&tenants, CoreType::Small);
+        let mut ps = ProcessorSharing::new();
+        for (t, time) in &[(1, 1.0), (2, 2.0), (1, 3.0)] {
+            ps.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, *time)));
+        }
+        let first = ps.pick_next_task(&ctx).unwrap();
+        ps.on_quantum_expiry(&ctx, 0, first);
+        ps.on_arrival(&ctx, 1, Box::new(Request::new(2, 0, 4.0)));
+        assert_eq!(
+            picks(&mut ps, &ctx),
+            vec![(2, 2.0), (1, 3.0), (1, 1.0), (2, 4.0)]
+        );
+
+        // A stolen request loses its turn; a removed tenant loses all of them.
+        for (t, time) in &[(1, 1.0), (2, 2.0), (2, 3.0)] {
+            ps.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, *time)));
+        }
+        assert_eq!(ps.steal_task(&ctx, false)[0].get_tenant(), 1);
+        ps.on_tenant_removed(&ctx, 1, &tenants[1].borrow());
+        assert!(ps.pick_next_task(&ctx).is_none());
+    }
+}
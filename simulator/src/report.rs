@@ -0,0 +1,651 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::Config;
+use super::cycles::Clock;
+
+use common::histogram::Histogram;
+
+use std::fs;
+use std::path::Path;
+
+/// The latency percentiles of the completed requests, in micro-seconds.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Percentiles {
+    // The number of latency samples.
+    pub count: u64,
+
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub p999: f64,
+    pub p9999: f64,
+    pub max: f64,
+}
+
+impl Percentiles {
+    /// This method computes the percentiles of the latencies.
+    ///
+    /// # Arguments
+    /// `latencies`: The histogram of the latencies in CPU cycles.
+    /// `clock`: The virtual clock the latencies were counted with.
+    pub fn new(latencies: &Histogram, clock: &Clock) -> Percentiles {
+        let us = |q: f64| clock.to_seconds(latencies.value_at_quantile(q)) * 1e6;
+        Percentiles {
+            count: latencies.count(),
+            p50: us(0.5),
+            p90: us(0.9),
+            p99: us(0.99),
+            p999: us(0.999),
+            p9999: us(0.9999),
+            max: clock.to_seconds(latencies.max()) * 1e6,
+        }
+    }
+}
+
+/// One completed request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    // The tenant which issued the request.
+    pub tenant: u32,
+
+    // The time from the arrival to the completion of the request in CPU cycles.
+    pub latency: u64,
+
+    // The service time of the request in micro-seconds.
+    pub service_us: f64,
+}
+
+/// The completed requests of one tenant.
+#[derive(Clone, Debug)]
+pub struct TenantStats {
+    // The latencies in CPU cycles of the short and of the long requests.
+    pub latencies: [Histogram; 2],
+
+    // The sum of the slowdowns, i.e. latency / service time, of the requests.
+    slowdown: f64,
+
+    // The number of requests in `slowdown`; requests without a service time have no slowdown.
+    slowdown_count: u64,
+}
+
+impl TenantStats {
+    /// # Arguments
+    /// `precision`: The precision of the latency histograms; see `Histogram::new()`.
+    pub fn new(precision: u32) -> TenantStats {
+        TenantStats {
+            latencies: [Histogram::new(precision), Histogram::new(precision)],
+            slowdown: 0.0,
+            slowdown_count: 0,
+        }
+    }
+
+    /// This method records a completed request of this tenant.
+    ///
+    /// # Arguments
+    /// `sample`: The completed request.
+    /// `long`: True if it is a long request.
+    /// `clock`: The virtual clock the latency was counted with.
+    pub fn record(&mut self, sample: &Sample, long: bool, clock: &Clock) {
+        self.latencies[long as usize].record(sample.latency);
+        if sample.service_us > 0.0 {
+            self.slowdown += clock.to_seconds(sample.latency) * 1e6 / sample.service_us;
+            self.slowdown_count += 1;
+        }
+    }
+
+    /// The number of completed requests.
+    pub fn count(&self) -> u64 {
+        self.latencies[0].count() + self.latencies[1].count()
+    }
+
+    /// The mean slowdown of the requests; zero if there are none.
+    pub fn mean_slowdown(&self) -> f64 {
+        if self.slowdown_count == 0 {
+            return 0.0;
+        }
+        self.slowdown / self.slowdown_count as f64
+    }
+}
+
+/// The latency and slowdown of one tenant.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TenantReport {
+    pub tenant: u32,
+
+    // The latency percentiles over all the requests of the tenant.
+    pub latency: Percentiles,
+
+    // The latency percentiles of the short and the long requests of the tenant.
+    pub short: Percentiles,
+    pub long: Percentiles,
+
+    // The mean of latency / service time over the requests of the tenant.
+    pub mean_slowdown: f64,
+}
+
+/// This function computes Jain's fairness index, `(sum x)^2 / (n * sum x^2)`; it is 1 when all
+/// the values are equal and 1/n when one value takes everything.
+///
+/// # Arguments
+/// `values`: The share of each tenant; larger is better.
+pub fn jain_index(values: &[f64]) -> f64 {
+    let sum: f64 = values.iter().sum();
+    let squares: f64 = values.iter().map(|x| x * x).sum();
+    if squares == 0.0 {
+        return 1.0;
+    }
+    sum * sum / (values.len() as f64 * squares)
+}
+
+/// The counters of one core.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CoreReport {
+    pub core_id: u32,
+
+    // Small or Large.
+    pub core_type: String,
+
+    // The NUMA node of the core.
+    pub numa_node: usize,
+
+    // The number of requests completed by the core.
+    pub requests: u64,
+
+    // The completed requests per second.
+    pub throughput: f64,
+
+    pub num_context_switches: u64,
+    pub num_mpk_switches: u64,
+    pub num_vmfunc_switches: u64,
+    pub num_preemptions: u64,
+
+    // The number of times the core stole requests from another core.
+    pub num_steals: u64,
+
+    // The number of tenants migrated to the core.
+    pub num_migrations: u64,
+
+    // The number of switches to a tenant without an MPK key or an EPTP list entry.
+    pub num_key_misses: u64,
+
+    // The number of switches to a tenant whose memory is on another NUMA node.
+    pub num_numa_switches: u64,
+
+    // The CPU cycles lost to cache and TLB misses after the switches.
+    pub cold_cycles: u64,
+
+    // The CPU cycles spent running the requests.
+    pub busy_cycles: u64,
+
+    // The CPU cycles spent in tenant switches, preemptions, steals, migrations, key misses, remote
+    // NUMA accesses and cache misses.
+    pub overhead_cycles: u64,
+
+    // The simulated time of the core in CPU cycles; the rest of it was idle.
+    pub total_cycles: u64,
+}
+
+impl CoreReport {
+    /// This method computes the counters over the measurement window.
+    ///
+    /// # Arguments
+    /// `start`: The counters of the core at the start of the window.
+    /// `window_start`: The time-stamp, in CPU cycles, at which the window started.
+    /// `window_end`: The time-stamp, in CPU cycles, at which the window ended; the current time
+    ///               if the run stopped before the end of the window.
+    /// `clock`: The virtual clock of the core.
+    pub fn since(
+        &self,
+        start: &CoreReport,
+        window_start: u64,
+        window_end: u64,
+        clock: &Clock,
+    ) -> CoreReport {
+        let requests = self.requests - start.requests;
+        let total_cycles = self
+            .total_cycles
+            .min(window_end)
+            .saturating_sub(window_start);
+        let window = window_end.saturating_sub(window_start);
+        let mut throughput = 0.0;
+        if window > 0 {
+            throughput = requests as f64 / clock.to_seconds(window);
+        }
+
+        CoreReport {
+            core_id: self.core_id,
+            core_type: self.core_type.clone(),
+            numa_node: self.numa_node,
+            requests,
+            throughput,
+            num_context_switches: self.num_context_switches - start.num_context_switches,
+            num_mpk_switches: self.num_mpk_switches - start.num_mpk_switches,
+            num_vmfunc_switches: self.num_vmfunc_switches - start.num_vmfunc_switches,
+            num_preemptions: self.num_preemptions - start.num_preemptions,
+            num_steals: self.num_steals - start.num_steals,
+            num_migrations: self.num_migrations - start.num_migrations,
+            num_key_misses: self.num_key_misses - start.num_key_misses,
+            num_numa_switches: self.num_numa_switches - start.num_numa_switches,
+            cold_cycles: self.cold_cycles - start.cold_cycles,
+            busy_cycles: self.busy_cycles - start.busy_cycles,
+            overhead_cycles: self.overhead_cycles - start.overhead_cycles,
+            total_cycles,
+        }
+    }
+
+    /// This method computes the context-switches per completed request, in percent; zero for a
+    /// core which completed nothing, like an idle or a Minos large core in a short window.
+    pub fn context_switch_percent(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        (self.num_context_switches as f64 / self.requests as f64) * 100.0
+    }
+
+    /// This method summarizes the counters of the core in one line.
+    ///
+    /// # Arguments
+    /// `clock`: The virtual clock of the core.
+    pub fn summary(&self, clock: &Clock) -> String {
+        format!(
+            "Throughput {:.2} Context-Switches(%) {:.2} Execution-Time(sec) {:.2} CS-Time(sec) {:.2} Total-Time(sec) {:.2}",
+            self.throughput,
+            self.context_switch_percent(),
+            self.requests as f64 / 1e6,
+            clock.to_seconds(self.overhead_cycles),
+            clock.to_seconds(self.total_cycles)
+        )
+    }
+}
+
+/// The counters of the central dispatcher of the Shinjuku policy.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DispatcherReport {
+    // The number of requests handed to the workers; a preempted request counts every time.
+    pub requests: u64,
+
+    // The fraction of the time the dispatcher was busy; close to one when it saturates.
+    pub utilization: f64,
+
+    // The longest the global queue has been over the whole run.
+    pub max_queue: usize,
+
+    // The CPU cycles spent handing out the requests.
+    pub busy_cycles: u64,
+
+    // The simulated time in CPU cycles.
+    pub total_cycles: u64,
+}
+
+impl DispatcherReport {
+    /// This method computes the counters over the measurement window.
+    ///
+    /// # Arguments
+    /// `start`: The counters of the dispatcher at the start of the window.
+    /// `window_start`: The time-stamp, in CPU cycles, at which the window started.
+    /// `window_end`: The time-stamp, in CPU cycles, at which the window ended; the current time
+    ///               if the run stopped before the end of the window.
+    pub fn since(
+        &self,
+        start: &DispatcherReport,
+        window_start: u64,
+        window_end: u64,
+    ) -> DispatcherReport {
+        let busy_cycles = self.busy_cycles - start.busy_cycles;
+        let total_cycles = self
+            .total_cycles
+            .min(window_end)
+            .saturating_sub(window_start);
+        let window = window_end.saturating_sub(window_start);
+        let mut utilization = 0.0;
+        if window > 0 {
+            utilization = busy_cycles as f64 / window as f64;
+        }
+
+        DispatcherReport {
+            requests: self.requests - start.requests,
+            utilization,
+            max_queue: self.max_queue,
+            busy_cycles,
+            total_cycles,
+        }
+    }
+}
+
+/// The machine-readable results of one simulator run.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Report {
+    // The configuration which produced these results.
+    pub config: serde_json::Value,
+
+    // The latency percentiles over all the cores.
+    pub latency: Percentiles,
+
+    // The latency percentiles of the short and the long requests; see `minos_threshold_us`.
+    pub short: Percentiles,
+    pub long: Percentiles,
+
+    // Jain's fairness index over the inverse of the mean slowdown of each tenant.
+    pub fairness: f64,
+
+    // The latency and slowdown of each tenant with completed requests.
+    pub tenants: Vec<TenantReport>,
+
+    // The completed requests per second, summed over all the cores.
+    pub throughput: f64,
+
+    // The counters of each core.
+    pub cores: Vec<CoreReport>,
+
+    // The counters of the central dispatcher; only with the Shinjuku policy.
+    pub dispatcher: Option<DispatcherReport>,
+}
+
+/// The columns of `cores.csv`.
+const CORES_HEADER: &str = "core_id,core_type,numa_node,requests,throughput,num_context_switches,\
+num_mpk_switches,num_vmfunc_switches,num_preemptions,num_steals,\
+num_migrations,num_key_misses,num_numa_switches,cold_cycles,busy_cycles,overhead_cycles,\
+total_cycles";
+
+/// The columns of `results.csv`.
+const RESULTS_HEADER: &str = "seed,num_tenants,req_rate,isolation,policy,throughput,count,\
+p50_us,p90_us,p99_us,p999_us,p9999_us,max_us,fairness";
+
+/// The columns of `tenants.csv`.
+const TENANTS_HEADER: &str = "tenant,count,p50_us,p90_us,p99_us,p999_us,max_us,mean_slowdown,\
+short_count,short_p50_us,short_p90_us,short_p99_us,short_p999_us,short_max_us,\
+long_count,long_p50_us,long_p90_us,long_p99_us,long_p999_us,long_max_us";
+
+impl Report {
+    /// This method builds the report of a run.
+    ///
+    /// # Arguments
+    /// `config`: The configuration of the run.
+    /// `latencies`: The latencies of all the requests in CPU cycles.
+    /// `classes`: The latencies of the short and of the long requests in CPU cycles.
+    /// `cores`: The counters of each core.
+    /// `tenants`: The completed requests of each tenant, indexed by tenant-id - 1.
+    pub fn new(
+        config: &Config,
+        latencies: &Histogram,
+        classes: &[Histogram; 2],
+        cores: Vec<CoreReport>,
+        tenants: &[TenantStats],
+    ) -> Report {
+        let clock = config.clock();
+        let mut reports = Vec::with_capacity(tenants.len());
+        for (i, stats) in tenants.iter().enumerate() {
+            if stats.count() == 0 {
+                continue;
+            }
+
+            let mut all = stats.latencies[0].clone();
+            all.merge(&stats.latencies[1]);
+            reports.push(TenantReport {
+                tenant: i as u32 + 1,
+                latency: Percentiles::new(&all, &clock),
+                short: Percentiles::new(&stats.latencies[0], &clock),
+                long: Percentiles::new(&stats.latencies[1], &clock),
+                mean_slowdown: stats.mean_slowdown(),
+            });
+        }
+
+        let shares: Vec<f64> = reports
+            .iter()
+            .filter(|t| t.mean_slowdown > 0.0)
+            .map(|t| 1.0 / t.mean_slowdown)
+            .collect();
+
+        Report {
+            config: serde_json::to_value(config).unwrap_or(serde_json::Value::Null),
+            latency: Percentiles::new(latencies, &clock),
+            short: Percentiles::new(&classes[0], &clock),
+            long: Percentiles::new(&classes[1], &clock),
+            fairness: jain_index(&shares),
+            tenants: reports,
+            throughput: cores.iter().map(|c| c.throughput).sum(),
+            cores,
+            dispatcher: None,
+        }
+    }
+
+    /// This method formats the report as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("Failure serializing report: {}", e))
+    }
+
+    /// This method formats the summary of the run as a CSV header and one row.
+    pub fn to_csv(&self) -> String {
+        let field = |key: &str| match self.config.get(key) {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => String::new(),
+        };
+        let l = &self.latency;
+        format!(
+            "{}\n{},{},{},{},{},{:.2},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.4}\n",
+            RESULTS_HEADER,
+            field("seed"),
+            field("num_tenants"),
+            field("req_rate"),
+            field("isolation"),
+            field("policy"),
+            self.throughput,
+            l.count,
+            l.p50,
+            l.p90,
+            l.p99,
+            l.p999,
+            l.p9999,
+            l.max,
+            self.fairness
+        )
+    }
+
+    /// This method formats the per-core counters as CSV, with one row per core.
+    pub fn cores_csv(&self) -> String {
+        let mut csv = String::from(CORES_HEADER);
+        csv.push('\n');
+        for c in &self.cores {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.2},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                c.core_id,
+                c.core_type,
+                c.numa_node,
+                c.requests,
+                c.throughput,
+                c.num_context_switches,
+                c.num_mpk_switches,
+                c.num_vmfunc_switches,
+                c.num_preemptions,
+                c.num_steals,
+                c.num_migrations,
+                c.num_key_misses,
+                c.num_numa_switches,
+                c.cold_cycles,
+                c.busy_cycles,
+                c.overhead_cycles,
+                c.total_cycles
+            ));
+        }
+        csv
+    }
+
+    /// This method formats the per-tenant latencies as CSV, with one row per tenant; the
+    /// percentiles of the short and the long requests follow the ones of all the requests.
+    pub fn tenants_csv(&self) -> String {
+        let columns = |l: &Percentiles| {
+            format!(
+                "{},{:.3},{:.3},{:.3},{:.3},{:.3}",
+                l.count, l.p50, l.p90, l.p99, l.p999, l.max
+            )
+        };
+        let mut csv = String::from(TENANTS_HEADER);
+        csv.push('\n');
+        for t in &self.tenants {
+            csv.push_str(&format!(
+                "{},{},{:.3},{},{}\n",
+                t.tenant,
+                columns(&t.latency),
+                t.mean_slowdown,
+                columns(&t.short),
+                columns(&t.long)
+            ));
+        }
+        csv
+    }
+
+    /// This method writes `results.json`, `results.csv`, `cores.csv` and `tenants.csv` to the
+    /// given directory.
+    ///
+    /// # Arguments
+    /// `dir`: The directory for the results; it must already exist.
+    pub fn write(&self, dir: &Path) -> Result<(), String> {
+        let files = vec![
+            ("results.json", self.to_json()?),
+            ("results.csv", self.to_csv()),
+            ("cores.csv", self.cores_csv()),
+            ("tenants.csv", self.tenants_csv()),
+        ];
+        for (name, contents) in files {
+            let filename = dir.join(name);
+            fs::write(&filename, contents)
+                .map_err(|e| format!("Failure writing {}: {}", filename.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let clock = Clock::default();
+        let us = clock.cycles_per_second() / 1000000;
+        let mut latencies = Histogram::new(7);
+        for i in 1..=1000 {
+            latencies.record(i * us);
+        }
+        let p = Percentiles::new(&latencies, &clock);
+        let close = |a: f64, b: f64| (a / b - 1.0).abs() <= 1.0 / 128.0;
+        assert_eq!(p.count, 1000);
+        assert!(close(p.p50, 500.0));
+        assert!(close(p.p99, 990.0));
+        assert!(close(p.p999, 999.0));
+        assert!(close(p.p9999, 1000.0));
+        assert!((p.max - 1000.0).abs() < 1e-6);
+        assert_eq!(
+            Percentiles::new(&Histogram::new(7), &clock),
+            Percentiles::default()
+        );
+    }
+
+    #[test]
+    fn test_tenant_classes() {
+        let config: Config = toml::from_str(
+            "num_cores = 1
+            large_cores = 0
+            num_tenants = 2
+            tenant_skew = 0.9
+            num_reqs = 10
+            num_resps = 10
+            req_rate = 1000
+            batching = true
+            isolation = \"NoIsolation\"
+            distribution = \"Uniform\"
+            policy = \"RoundRobin\"",
+        )
+        .unwrap();
+
+        // The second tenant has one short and one long request; the first has none.
+        let clock = Clock::default();
+        let us = clock.cycles_per_second() / 1000000;
+        let mut tenants = vec![TenantStats::new(7), TenantStats::new(7)];
+        for (latency, long) in &[(10, false), (1000, true)] {
+            let sample = Sample {
+                tenant: 2,
+                latency: latency * us,
+                service_us: 1.0,
+            };
+            tenants[1].record(&sample, *long, &clock);
+        }
+        let classes = [Histogram::new(7), Histogram::new(7)];
+        let report = Report::new(&config, &Histogram::new(7), &classes, vec![], &tenants);
+
+        assert_eq!(report.tenants.len(), 1);
+        let tenant = &report.tenants[0];
+        assert_eq!(
+            (tenant.latency.count, tenant.short.count, tenant.long.count),
+            (2, 1, 1)
+        );
+        assert!(tenant.short.max < 11.0 && tenant.long.max > 990.0);
+
+        let csv = report.tenants_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].split(',').count(), lines[1].split(',').count());
+        assert!(lines[1].starts_with("2,2,"));
+    }
+
+    #[test]
+    fn test_window_throughput() {
+        let clock = Clock::default();
+        let second = clock.cycles_per_second();
+        let core = |requests: u64, total_cycles: u64| CoreReport {
+            core_id: 0,
+            core_type: "Small".to_string(),
+            numa_node: 0,
+            requests,
+            throughput: 0.0,
+            num_context_switches: 0,
+            num_mpk_switches: 0,
+            num_vmfunc_switches: 0,
+            num_preemptions: 0,
+            num_steals: 0,
+            num_migrations: 0,
+            num_key_misses: 0,
+            num_numa_switches: 0,
+            cold_cycles: 0,
+            busy_cycles: 0,
+            overhead_cycles: 0,
+            total_cycles,
+        };
+
+        // A core which went idle half-way through a two second window.
+        let idle = core(300, 2 * second).since(&core(100, second), second, 3 * second, &clock);
+        assert_eq!(idle.requests, 200);
+        assert!((idle.throughput - 100.0).abs() < 1e-6);
+        assert_eq!(idle.total_cycles, second);
+
+        // A core whose last event was before the window.
+        let early = core(100, second / 2).since(&core(100, second / 2), second, 3 * second, &clock);
+        assert_eq!(
+            (early.requests, early.throughput, early.total_cycles),
+            (0, 0.0, 0)
+        );
+        assert_eq!(early.context_switch_percent(), 0.0);
+        assert!(!early.summary(&clock).contains("NaN"));
+    }
+
+    #[test]
+    fn test_jain_index() {
+        assert!((jain_index(&[2.0, 2.0, 2.0, 2.0]) - 1.0).abs() < 1e-9);
+        assert!((jain_index(&[1.0, 0.0, 0.0, 0.0]) - 0.25).abs() < 1e-9);
+        assert!((jain_index(&[1.0, 3.0]) - 0.8).abs() < 1e-9);
+    }
+}
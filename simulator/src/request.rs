@@ -13,11 +13,11 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
-use super::{config::Isolation, consts, cores::CoreType, cycles};
+use super::cycles::Clock;
 
 pub struct Request {
     // This task belong to tenant `tenant_id`.
-    tenant_id: u16,
+    tenant_id: u32,
 
     // The starting time for this task.
     start_time: u64,
@@ -41,7 +41,7 @@ pub enum TaskState {
 }
 
 impl Request {
-    pub fn new(tenant: u16, rdstc: u64, task_time: f64) -> Request {
+    pub fn new(tenant: u32, rdstc: u64, task_time: f64) -> Request {
         Request {
             tenant_id: tenant,
             start_time: rdstc,
@@ -51,58 +51,51 @@ impl Request {
         }
     }
 
-    pub fn run(&mut self, isolation: &Isolation, coretype: CoreType) -> (u64, TaskState) {
+    /// This method runs the task for one time quanta, or until it completes.
+    ///
+    /// # Arguments
+    /// `quanta_us`: The time quanta of the core in micro-seconds.
+    /// `preemption_cycles`: The CPU cycles spent if the task is preempted.
+    /// `cold_cycles`: The CPU cycles lost to cache and TLB misses; they add to the remaining time.
+    /// `clock`: The virtual clock of the core.
+    ///
+    /// # Return
+    /// The CPU cycles spent, including the preemption overhead, and the state of the task.
+    pub fn run(
+        &mut self,
+        quanta_us: f64,
+        preemption_cycles: u64,
+        cold_cycles: u64,
+        clock: &Clock,
+    ) -> (u64, TaskState) {
         let mut time = 0;
-        let quant_time;
-        match coretype {
-            CoreType::Small => {
-                quant_time = consts::QUANTA_TIME;
-            }
-
-            CoreType::Large => {
-                quant_time = consts::LARGE_QUNATA_TIME;
-            }
-        }
-
+        self.remaining_time += cold_cycles as f64 / clock.cycles_per_us();
+        let quant_time = quanta_us;
         if self.remaining_time() <= quant_time {
-            time += ((cycles::cycles_per_second() as f64 / 1e6) * self.remaining_time) as u64;
+            time += (clock.cycles_per_us() * self.remaining_time) as u64;
             self.taskstate = TaskState::Completed;
         } else {
-            time += ((cycles::cycles_per_second() as f64 / 1e6) * quant_time) as u64;
+            time += (clock.cycles_per_us() * quant_time) as u64;
             self.remaining_time -= quant_time;
             self.taskstate = TaskState::Preempted;
-
-            match isolation {
-                Isolation::NoIsolation => {
-                    time += consts::NOISOLATION_PREEMPTION_OVERHEAD_CYCLES;
-                }
-                Isolation::PageTableIsolation => {
-                    time += consts::PAGING_PREEMPTION_OVERHEAD_CYCLES;
-                }
-                Isolation::MpkIsolation => {
-                    time += consts::MPK_PREEMPTION_OVERHEAD_CYCLES;
-                }
-                Isolation::VmfuncIsolation => {
-                    time += consts::VMFUNC_PREEMPTION_OVERHEAD_CYCLES;
-                }
-            }
+            time += preemption_cycles;
         }
         (time, self.taskstate)
     }
 
-    pub fn get_tenant(&self) -> u16 {
-        self.tenant_id.clone()
+    pub fn get_tenant(&self) -> u32 {
+        self.tenant_id
     }
 
     pub fn start_time(&self) -> u64 {
-        self.start_time.clone()
+        self.start_time
     }
 
     pub fn max_time(&self) -> f64 {
-        self.max_time.clone()
+        self.max_time
     }
 
     pub fn remaining_time(&self) -> f64 {
-        self.remaining_time.clone()
+        self.remaining_time
     }
 }
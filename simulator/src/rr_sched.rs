@@ -13,39 +13,152 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
-use super::cores::CoreType;
 use super::request::Request;
-use super::sched::Scheduler;
-
-use std::collections::VecDeque;
+use super::sched::{cursor_added, cursor_removed, take, Context, Scheduler};
+use super::tenant::Tenant;
 
+/// The core goes through its tenants one by one, in the order of their ids, and runs up to
+/// `batch_size` requests of each tenant at a time. The requests of a tenant run in the order they
+/// arrived; the preempted ones go to the tail.
 pub struct RoundRobin {
-    // Task runqueue for this tenant.
-    pub rq: VecDeque<Box<Request>>,
+    // The index of the tenant which is being served in the current batch.
+    cursor: usize,
+
+    // The number of tasks picked from the tenant at `cursor` in the current batch.
+    batched: usize,
+
+    // The maximum number of tasks picked from a tenant in one batch.
+    batch_size: usize,
 }
 
 impl RoundRobin {
-    pub fn new() -> RoundRobin {
+    pub fn new(batch_size: usize) -> RoundRobin {
         RoundRobin {
-            rq: VecDeque::with_capacity(32),
+            cursor: 0,
+            batched: 0,
+            batch_size,
         }
     }
+
+    /// This method picks the next task from the given run-queue of the tenants, in the
+    /// round-robin order.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core.
+    /// `queue`: The run-queue of the tenants to pick from.
+    pub fn pick_from(&mut self, ctx: &Context, queue: usize) -> Option<Box<Request>> {
+        self.pick_where(ctx, queue, |_| true)
+    }
+
+    /// This method picks the next task from the given run-queue of the eligible tenants, in the
+    /// round-robin order; the other tenants are skipped.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core.
+    /// `queue`: The run-queue of the tenants to pick from.
+    /// `eligible`: Tells if a tenant can run now; e.g. if it is in the highest priority class.
+    pub fn pick_where(
+        &mut self,
+        ctx: &Context,
+        queue: usize,
+        eligible: impl Fn(&Tenant) -> bool,
+    ) -> Option<Box<Request>> {
+        let num_tenants = ctx.tenants.len();
+        if num_tenants == 0 {
+            return None;
+        }
+
+        // Visit the current tenant once more after wrapping around, as it may be the only one
+        // with pending tasks.
+        for _t in 0..num_tenants + 1 {
+            let mut tenant = ctx.tenants[self.cursor].borrow_mut();
+            if self.batched < self.batch_size && eligible(&tenant) {
+                if let Some(task) = tenant.pop_front(queue) {
+                    self.batched += 1;
+                    return Some(task);
+                }
+            }
+            self.cursor = (self.cursor + 1) % num_tenants;
+            self.batched = 0;
+        }
+        None
+    }
+
+    /// This method removes tasks from the given run-queue; the tenant which this core would serve
+    /// next is picked first.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core.
+    /// `queue`: The run-queue of the tenants to take from.
+    /// `all`: Take all the tasks of the tenant in the run-queue; otherwise just one.
+    // The tasks stay boxed as they move between the run-queues of the cores.
+    #[allow(clippy::vec_box)]
+    pub fn steal_from(&mut self, ctx: &Context, queue: usize, all: bool) -> Vec<Box<Request>> {
+        self.steal_where(ctx, queue, all, |_| true)
+    }
+
+    /// This method removes tasks from the given run-queue of the eligible tenants; the tenant
+    /// which this core would serve next is picked first.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core.
+    /// `queue`: The run-queue of the tenants to take from.
+    /// `all`: Take all the tasks of the tenant in the run-queue; otherwise just one.
+    /// `eligible`: Tells if the tasks of a tenant can be taken.
+    #[allow(clippy::vec_box)]
+    pub fn steal_where(
+        &mut self,
+        ctx: &Context,
+        queue: usize,
+        all: bool,
+        eligible: impl Fn(&Tenant) -> bool,
+    ) -> Vec<Box<Request>> {
+        let num_tenants = ctx.tenants.len();
+        for i in 1..num_tenants + 1 {
+            let index = (self.cursor + i) % num_tenants;
+            if !eligible(&ctx.tenants[index].borrow()) {
+                continue;
+            }
+            let stolen = take(ctx, index, queue, all);
+            if !stolen.is_empty() {
+                return stolen;
+            }
+        }
+        Vec::new()
+    }
 }
 
 impl Scheduler for RoundRobin {
     // Lookup the `Scheduler` trait for documentation on this method.
-    fn create_task(&mut self, rdtsc: u64, task_time: f64, tenant_id: u16) {
-        let req = Box::new(Request::new(tenant_id, rdtsc, task_time));
-        self.rq.push_back(req);
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        self.pick_from(ctx, 0)
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        self.steal_from(ctx, 0, all)
     }
 
     // Lookup the `Scheduler` trait for documentation on this method.
-    fn pick_next_task(&mut self, _type: CoreType) -> Option<Box<Request>> {
-        self.rq.pop_front()
+    fn on_tenant_removed(&mut self, ctx: &Context, index: usize, _tenant: &Tenant) {
+        // Keep serving the batch of the same tenant; or start a new batch with the next one.
+        if cursor_removed(&mut self.cursor, index, ctx.tenants.len()) {
+            self.batched = 0;
+        }
     }
 
     // Lookup the `Scheduler` trait for documentation on this method.
-    fn enqueue_task(&mut self, req: Box<Request>) {
-        self.rq.push_back(req);
+    fn on_tenant_added(&mut self, ctx: &Context, index: usize) {
+        cursor_added(&mut self.cursor, index, ctx.tenants.len());
     }
 }
@@ -15,28 +15,345 @@
 
 use super::cores::CoreType;
 use super::request::Request;
+use super::tenant::Tenant;
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The view of a core which the scheduler of the core gets on each decision.
+pub struct Context<'a> {
+    // The current time-stamp on the core.
+    pub now: u64,
+
+    // The id of the core.
+    pub core_id: u32,
+
+    // Type of the core; Small or Large.
+    pub core_type: CoreType,
+
+    // The tenants of the core, ordered by tenant-id; each one holds its own run-queues, which
+    // move with the tenant to another core.
+    pub tenants: &'a [Rc<RefCell<Tenant>>],
+
+    // The tenant whose request ran last on the core, if any.
+    pub active_tenant: Option<u32>,
+
+    // The number of requests waiting on the core; including the remote ones.
+    pub outstanding: usize,
+
+    // The number of requests of the tenants of other cores waiting on the core; they run before
+    // the ones the scheduler picks.
+    pub remote: usize,
+}
+
+/// The scheduler of a core. It decides which run-queue of its tenant each request goes to, and
+/// which request the core runs next. The scheduler sees all the tenants of its core; the tenants
+/// are referred to by their index in `Context::tenants`.
 pub trait Scheduler {
-    /// This method creates a new task and adds that to the first run-queue.
+    /// This method queues a new request.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core.
+    /// `index`: The index of the tenant of the request.
+    /// `req`: The new request.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>);
+
+    /// This method queues a request whose time quanta expired on this core.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core.
+    /// `index`: The index of the tenant of the request.
+    /// `req`: The preempted request.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>);
+
+    /// This method is called when a request completes on this core; including the requests of
+    /// the tenants of other cores.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core.
+    /// `req`: The completed request.
+    fn on_completion(&mut self, _ctx: &Context, _req: &Request) {}
+
+    /// This method is called when the core switches to another tenant, before the request of
+    /// that tenant runs.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core; `active_tenant` is still the previous tenant.
+    /// `tenant`: The tenant-id of the next tenant.
+    fn on_tenant_switch(&mut self, _ctx: &Context, _tenant: u32) {}
+
+    /// This method picks the next request to run; the core is idle.
     ///
     /// # Arguments
-    /// `rdtsc`: The CPU time at which the task was created.
-    /// `task_time`: The amount of CPU Cycles this task needs to complete.
-    /// `tenant_id`: The tells the tenant for which this was created.
-    fn create_task(&mut self, rdtsc: u64, task_time: f64, tenant_id: u16);
+    /// `ctx`: The state of the core.
+    ///
+    /// # Return
+    /// A request of one of the tenants of the core, if there is any to run.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>>;
+
+    /// This method removes runnable requests, so that an idle core can run them instead.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core.
+    /// `all`: Take all the runnable requests of a tenant; otherwise just one.
+    ///
+    /// # Return
+    /// The removed requests; all of the same tenant.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>>;
 
-    /// This method picks the next task to execute on the CPU.
+    /// This method tells how many requests `steal_task` can take from the core, if only the ones
+    /// which the core doesn't run itself can move; e.g. the ones for the cores of another type.
     ///
     /// # Arguments
-    /// `coretype`: The current core type value; used in deciding which task to pick next.
+    /// `ctx`: The state of the core.
     ///
     /// # Return
-    /// Return a task to execute on the current CPU.
-    fn pick_next_task(&mut self, coretype: CoreType) -> Option<Box<Request>>;
+    /// The number of requests; None if all the requests waiting in the run-queues can move.
+    fn stealable(&self, _ctx: &Context) -> Option<usize> {
+        None
+    }
 
-    /// This method decides where to execute the task after preemption.
+    /// This method is called after a tenant is removed from the core, along with its run-queues.
     ///
-    /// # Argument
-    /// `req`: The preempted task.
-    fn enqueue_task(&mut self, req: Box<Request>);
+    /// # Arguments
+    /// `ctx`: The state of the core; without the tenant.
+    /// `index`: The index the tenant had.
+    /// `tenant`: The removed tenant.
+    fn on_tenant_removed(&mut self, _ctx: &Context, _index: usize, _tenant: &Tenant) {}
+
+    /// This method is called after a tenant is added to the core, along with its run-queues.
+    ///
+    /// # Arguments
+    /// `ctx`: The state of the core; with the tenant.
+    /// `index`: The index of the tenant.
+    fn on_tenant_added(&mut self, _ctx: &Context, _index: usize) {}
+}
+
+/// This function keeps a cursor over the tenants of a core on the same tenant after another one
+/// is removed; or moves it to the next tenant if the removed one was at the cursor.
+///
+/// # Arguments
+/// `cursor`: The index of the tenant at the cursor.
+/// `index`: The index the removed tenant had.
+/// `num_tenants`: The number of tenants left.
+///
+/// # Return
+/// True if the removed tenant was the one at the cursor.
+pub fn cursor_removed(cursor: &mut usize, index: usize, num_tenants: usize) -> bool {
+    let moved = index == *cursor;
+    if index < *cursor {
+        *cursor -= 1;
+    }
+    if *cursor >= num_tenants {
+        *cursor = 0;
+    }
+    moved
+}
+
+/// This function keeps a cursor over the tenants of a core on the same tenant after another one
+/// is added.
+///
+/// # Arguments
+/// `cursor`: The index of the tenant at the cursor.
+/// `index`: The index of the added tenant.
+/// `num_tenants`: The number of tenants; with the added one.
+pub fn cursor_added(cursor: &mut usize, index: usize, num_tenants: usize) {
+    if index <= *cursor && num_tenants > 1 {
+        *cursor += 1;
+    }
+}
+
+/// This function tells how long a request runs once it is picked; the rest of it, or one time
+/// quanta of the core, in micro-seconds.
+///
+/// # Arguments
+/// `req`: The request.
+/// `quanta_us`: The time quanta of the core in micro-seconds.
+pub fn run_time(req: &Request, quanta_us: f64) -> f64 {
+    req.remaining_time().min(quanta_us)
+}
+
+/// This function finds the tenant whose request at the head of a run-queue has the smallest key.
+///
+/// # Arguments
+/// `ctx`: The state of the core.
+/// `queue`: The run-queue of the tenants to look at.
+/// `key`: The key of the request at the head of a run-queue; e.g. its remaining time.
+///
+/// # Return
+/// The index of the tenant; the lowest one on a tie. None if the run-queues are empty.
+pub fn min_head<K: PartialOrd>(
+    ctx: &Context,
+    queue: usize,
+    key: impl Fn(&Tenant, &Request) -> K,
+) -> Option<usize> {
+    let mut best: Option<(usize, K)> = None;
+    for (index, tenant) in ctx.tenants.iter().enumerate() {
+        let tenant = tenant.borrow();
+        if let Some(req) = tenant.front(queue) {
+            let k = key(&tenant, req);
+            if best.as_ref().is_none_or(|(_, b)| k < *b) {
+                best = Some((index, k));
+            }
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
+/// This function finds the tenant with requests in a run-queue which has the largest credit; e.g.
+/// the one which is the furthest ahead in virtual time.
+///
+/// # Arguments
+/// `ctx`: The state of the core.
+/// `queue`: The run-queue of the tenants to look at.
+///
+/// # Return
+/// The index of the tenant; the highest one on a tie. None if the run-queues are empty.
+pub fn max_credit(ctx: &Context, queue: usize) -> Option<usize> {
+    ctx.tenants
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.borrow().len(queue) > 0)
+        .max_by(|a, b| a.1.borrow().credit.total_cmp(&b.1.borrow().credit))
+        .map(|(index, _)| index)
+}
+
+/// This function removes requests from the head of a run-queue of a tenant; e.g. to steal them.
+///
+/// # Arguments
+/// `ctx`: The state of the core.
+/// `index`: The index of the tenant.
+/// `queue`: The run-queue of the tenant.
+/// `all`: Take all the requests in the run-queue; otherwise just one.
+pub fn take(ctx: &Context, index: usize, queue: usize, all: bool) -> Vec<Box<Request>> {
+    let mut tenant = ctx.tenants[index].borrow_mut();
+    let mut taken = Vec::new();
+    while let Some(req) = tenant.pop_front(queue) {
+        taken.push(req);
+        if !all {
+            break;
+        }
+    }
+    taken
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::config::Config;
+
+    /// This function creates the tenants of a core; the tenant-ids 1 to `num_tenants`, with the
+    /// default SLO, priority and weight.
+    pub fn tenants(num_tenants: u32) -> Vec<Rc<RefCell<Tenant>>> {
+        let config: Config = toml::from_str(
+            "num_cores = 1
+            large_cores = 0
+            num_tenants = 1
+            tenant_skew = 0.9
+            num_reqs = 10
+            num_resps = 10
+            req_rate = 1000
+            batching = true
+            isolation = \"NoIsolation\"
+            distribution = \"Uniform\"
+            policy = \"RoundRobin\"",
+        )
+        .unwrap();
+        (1..=num_tenants)
+            .map(|t| Rc::new(RefCell::new(Tenant::new(t, &config))))
+            .collect()
+    }
+
+    /// This function creates the state of an idle core of the given type.
+    pub fn context(tenants: &[Rc<RefCell<Tenant>>], core_type: CoreType) -> Context<'_> {
+        Context {
+            now: 0,
+            core_id: 0,
+            core_type,
+            tenants,
+            active_tenant: None,
+            outstanding: 0,
+            remote: 0,
+        }
+    }
+
+    /// This function runs the requests the scheduler picks to completion, until it picks none.
+    ///
+    /// # Return
+    /// The tenant-id and the run time of each picked request, in order.
+    pub fn picks(sched: &mut dyn Scheduler, ctx: &Context) -> Vec<(u32, f64)> {
+        let mut picked = Vec::new();
+        while let Some(req) = sched.pick_next_task(ctx) {
+            picked.push((req.get_tenant(), req.remaining_time()));
+        }
+        picked
+    }
+
+    #[test]
+    fn test_min_head() {
+        // The smallest key wins, the lowest index on a tie; the tenants without requests don't.
+        let tenants = tenants(3);
+        let ctx = context(&tenants, CoreType::Small);
+        assert_eq!(min_head(&ctx, 0, |_, r| r.remaining_time()), None);
+        for (t, time) in &[(2, 5.0), (3, 5.0)] {
+            let req = Box::new(Request::new(*t, 0, *time));
+            tenants[*t as usize - 1].borrow_mut().push_back(0, req);
+        }
+        assert_eq!(min_head(&ctx, 0, |_, r| r.remaining_time()), Some(1));
+        assert_eq!(min_head(&ctx, 1, |_, r| r.remaining_time()), None);
+    }
+
+    #[test]
+    fn test_max_credit() {
+        // The largest credit among the tenants with requests wins, the highest index on a tie; a
+        // NaN credit is the largest instead of a panic.
+        let tenants = tenants(4);
+        let ctx = context(&tenants, CoreType::Small);
+        assert_eq!(max_credit(&ctx, 0), None);
+        for (t, credit) in &[(1, 2.0), (2, 2.0), (4, f64::NAN)] {
+            let mut tenant = tenants[*t as usize - 1].borrow_mut();
+            tenant.credit = *credit;
+            tenant.push_back(0, Box::new(Request::new(*t, 0, 1.0)));
+        }
+        tenants[2].borrow_mut().credit = 10.0;
+        assert_eq!(max_credit(&ctx, 0), Some(3));
+        tenants[3].borrow_mut().credit = 1.0;
+        assert_eq!(max_credit(&ctx, 0), Some(1));
+    }
+
+    #[test]
+    fn test_cursors() {
+        // Removing a tenant before the cursor shifts it; removing the one at the cursor moves
+        // it to the next tenant, wrapping around at the end.
+        let mut cursor = 2;
+        assert!(!cursor_removed(&mut cursor, 0, 3));
+        assert_eq!(cursor, 1);
+        assert!(cursor_removed(&mut cursor, 1, 2));
+        assert_eq!(cursor, 1);
+        assert!(cursor_removed(&mut cursor, 1, 1));
+        assert_eq!(cursor, 0);
+
+        // Adding a tenant at or before the cursor shifts it, unless it is the only tenant.
+        cursor_added(&mut cursor, 0, 1);
+        assert_eq!(cursor, 0);
+        cursor_added(&mut cursor, 0, 2);
+        assert_eq!(cursor, 1);
+        cursor_added(&mut cursor, 2, 3);
+        assert_eq!(cursor, 1);
+    }
+
+    #[test]
+    fn test_take() {
+        let tenants = tenants(1);
+        let ctx = context(&tenants, CoreType::Small);
+        for time in &[1.0, 2.0, 3.0] {
+            tenants[0]
+                .borrow_mut()
+                .push_back(0, Box::new(Request::new(1, 0, *time)));
+        }
+        assert_eq!(take(&ctx, 0, 0, false).len(), 1);
+        assert_eq!(take(&ctx, 0, 0, true).len(), 2);
+        assert!(take(&ctx, 0, 0, true).is_empty());
+        assert_eq!(run_time(&Request::new(1, 0, 3.0), 2.0), 2.0);
+    }
 }
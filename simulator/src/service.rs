@@ -0,0 +1,387 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::arrival::exponential;
+use super::config::Config;
+
+use std::f64::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+/// The distribution of the service time of the requests; all the times are in micro-seconds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum ServiceTime {
+    // Every request takes the same time.
+    Fixed {
+        time_us: f64,
+    },
+
+    // Exponentially distributed service times.
+    Exponential {
+        mean_us: f64,
+    },
+
+    // The request takes one of the modes, picked with the relative weights.
+    Bimodal {
+        modes_us: Vec<f64>,
+        weights: Vec<f64>,
+    },
+
+    // The logarithm of the service time is normally distributed with mean `mu` and standard
+    // deviation `sigma`.
+    LogNormal {
+        mu: f64,
+        sigma: f64,
+    },
+
+    // Pareto distribution with the minimum `scale_us` and the tail index `shape`.
+    Pareto {
+        scale_us: f64,
+        shape: f64,
+    },
+
+    // Pareto distribution truncated to `low_us..high_us`.
+    BoundedPareto {
+        low_us: f64,
+        high_us: f64,
+        shape: f64,
+    },
+
+    // Empirical distribution; `cdf[i]` is the probability of a service time up to `values_us[i]`.
+    // The service time is interpolated linearly between the points.
+    Empirical {
+        values_us: Vec<f64>,
+        cdf: Vec<f64>,
+    },
+}
+
+impl Default for ServiceTime {
+    fn default() -> ServiceTime {
+        // Short-running and long-running tasks.
+        ServiceTime::Bimodal {
+            modes_us: vec![1.0, 1.0],
+            weights: vec![99.9, 0.1],
+        }
+    }
+}
+
+/// The service time distribution for a range of tenants.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TenantServiceTime {
+    // The first tenant-id in the range.
+    pub first: u64,
+
+    // The last tenant-id in the range; inclusive.
+    pub last: u64,
+
+    // The distribution for these tenants.
+    #[serde(flatten)]
+    pub service_time: ServiceTime,
+}
+
+impl ServiceTime {
+    /// This method draws a service time.
+    ///
+    /// # Arguments
+    /// `rng`: The random number generator to use.
+    ///
+    /// # Return
+    /// The service time in micro-seconds.
+    pub fn sample(&self, rng: &mut StdRng) -> f64 {
+        match self {
+            ServiceTime::Fixed { time_us } => *time_us,
+
+            ServiceTime::Exponential { mean_us } => exponential(rng, *mean_us),
+
+            ServiceTime::Bimodal { modes_us, weights } => {
+                let total: f64 = weights.iter().sum();
+                let mut u = rng.gen::<f64>() * total;
+                for (mode, weight) in modes_us.iter().zip(weights) {
+                    if u < *weight {
+                        return *mode;
+                    }
+                    u -= weight;
+                }
+                modes_us[modes_us.len() - 1]
+            }
+
+            ServiceTime::LogNormal { mu, sigma } => {
+                // Box-Muller transform for a standard normal sample.
+                let u1: f64 = 1.0 - rng.gen::<f64>();
+                let u2: f64 = rng.gen();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+                (mu + sigma * z).exp()
+            }
+
+            ServiceTime::Pareto { scale_us, shape } => {
+                let u: f64 = 1.0 - rng.gen::<f64>();
+                scale_us / u.powf(1.0 / shape)
+            }
+
+            ServiceTime::BoundedPareto {
+                low_us,
+                high_us,
+                shape,
+            } => {
+                let u: f64 = rng.gen();
+                let (l, h) = (low_us.powf(*shape), high_us.powf(*shape));
+                (-(u * h - u * l - h) / (h * l)).powf(-1.0 / shape)
+            }
+
+            ServiceTime::Empirical { values_us, cdf } => {
+                let u: f64 = rng.gen();
+                if u <= cdf[0] {
+                    return values_us[0];
+                }
+                for i in 1..cdf.len() {
+                    if u <= cdf[i] {
+                        let f = (u - cdf[i - 1]) / (cdf[i] - cdf[i - 1]);
+                        return values_us[i - 1] + f * (values_us[i] - values_us[i - 1]);
+                    }
+                }
+                values_us[values_us.len() - 1]
+            }
+        }
+    }
+
+    /// This method computes the mean of the distribution in micro-seconds; infinite for a
+    /// Pareto distribution with a shape up to one.
+    pub fn mean(&self) -> f64 {
+        match self {
+            ServiceTime::Fixed { time_us } => *time_us,
+
+            ServiceTime::Exponential { mean_us } => *mean_us,
+
+            ServiceTime::Bimodal { modes_us, weights } => {
+                let total: f64 = weights.iter().sum();
+                modes_us
+                    .iter()
+                    .zip(weights)
+                    .map(|(m, w)| m * w)
+                    .sum::<f64>()
+                    / total
+            }
+
+            ServiceTime::LogNormal { mu, sigma } => (mu + sigma * sigma / 2.0).exp(),
+
+            ServiceTime::Pareto { scale_us, shape } => {
+                if *shape <= 1.0 {
+                    return f64::INFINITY;
+                }
+                scale_us * shape / (shape - 1.0)
+            }
+
+            ServiceTime::BoundedPareto {
+                low_us,
+                high_us,
+                shape,
+            } => {
+                let (l, h, a) = (*low_us, *high_us, *shape);
+                if (a - 1.0).abs() < 1e-9 {
+                    return h * l / (h - l) * (h / l).ln();
+                }
+                l.powf(a) / (1.0 - (l / h).powf(a)) * a / (a - 1.0)
+                    * (1.0 / l.powf(a - 1.0) - 1.0 / h.powf(a - 1.0))
+            }
+
+            ServiceTime::Empirical { values_us, cdf } => {
+                let mut mean = cdf[0] * values_us[0];
+                for i in 1..cdf.len() {
+                    mean += (cdf[i] - cdf[i - 1]) * (values_us[i - 1] + values_us[i]) / 2.0;
+                }
+                mean
+            }
+        }
+    }
+
+    /// This method returns the short mode, the long mode and the fraction of long requests of a
+    /// bimodal distribution; Minos partitions the cores based on these.
+    pub fn modes(&self) -> Option<(f64, f64, f64)> {
+        match self {
+            ServiceTime::Bimodal { modes_us, weights } => {
+                let total: f64 = weights.iter().sum();
+                Some((
+                    modes_us[0],
+                    modes_us[modes_us.len() - 1],
+                    weights[weights.len() - 1] / total,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// This method checks the parameters of the distribution.
+    pub fn validate(&self) -> Result<(), String> {
+        let valid = match self {
+            ServiceTime::Fixed { time_us } => *time_us >= 0.0,
+            ServiceTime::Exponential { mean_us } => *mean_us > 0.0,
+            ServiceTime::Bimodal { modes_us, weights } => {
+                !modes_us.is_empty()
+                    && modes_us.len() == weights.len()
+                    && modes_us.iter().all(|m| *m >= 0.0)
+                    && weights.iter().all(|w| *w >= 0.0)
+                    && weights.iter().sum::<f64>() > 0.0
+            }
+            ServiceTime::LogNormal { sigma, .. } => *sigma >= 0.0,
+            ServiceTime::Pareto { scale_us, shape } => *scale_us > 0.0 && *shape > 0.0,
+            ServiceTime::BoundedPareto {
+                low_us,
+                high_us,
+                shape,
+            } => *low_us > 0.0 && low_us < high_us && *shape > 0.0,
+            ServiceTime::Empirical { values_us, cdf } => {
+                !values_us.is_empty()
+                    && values_us.len() == cdf.len()
+                    && values_us.windows(2).all(|w| w[0] <= w[1])
+                    && cdf.windows(2).all(|w| w[0] < w[1])
+                    && cdf[0] >= 0.0
+                    && (cdf[cdf.len() - 1] - 1.0).abs() < 1e-9
+            }
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Invalid service time distribution {:?}", self))
+        }
+    }
+}
+
+/// The service time distributions of all the tenants.
+#[derive(Clone, Debug)]
+pub struct ServiceModel {
+    // The distribution for the tenants without their own.
+    default: ServiceTime,
+
+    // The distributions for ranges of tenants.
+    tenants: Vec<TenantServiceTime>,
+}
+
+impl ServiceModel {
+    pub fn new(config: &Config) -> ServiceModel {
+        ServiceModel {
+            default: config.service_time.clone(),
+            tenants: config.tenant_service_time.clone(),
+        }
+    }
+
+    /// This method draws a service time for a request of the given tenant.
+    ///
+    /// # Arguments
+    /// `tenant`: The tenant-id of the request.
+    /// `rng`: The random number generator to use.
+    ///
+    /// # Return
+    /// The service time in micro-seconds.
+    pub fn sample(&self, tenant: u32, rng: &mut StdRng) -> f64 {
+        self.distribution(tenant).sample(rng)
+    }
+
+    /// This method returns the mean service time, in micro-seconds, of the given tenant.
+    pub fn mean(&self, tenant: u32) -> f64 {
+        self.distribution(tenant).mean()
+    }
+
+    // The distribution of the given tenant.
+    fn distribution(&self, tenant: u32) -> &ServiceTime {
+        for t in &self.tenants {
+            if tenant as u64 >= t.first && tenant as u64 <= t.last {
+                return &t.service_time;
+            }
+        }
+        &self.default
+    }
+
+    /// This method returns the modes of the default distribution; see `ServiceTime::modes()`.
+    pub fn modes(&self) -> Option<(f64, f64, f64)> {
+        self.default.modes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::SeedableRng;
+
+    // The sample mean; also checks that it matches `ServiceTime::mean()`.
+    fn mean(service_time: ServiceTime) -> f64 {
+        let mut rng = StdRng::seed_from_u64(7);
+        let n = 200000;
+        let mean = (0..n).map(|_| service_time.sample(&mut rng)).sum::<f64>() / n as f64;
+        assert!(
+            (mean / service_time.mean() - 1.0).abs() < 0.03,
+            "{:?}",
+            service_time
+        );
+        mean
+    }
+
+    #[test]
+    fn test_sample_mean() {
+        let close = |a: f64, b: f64| (a / b - 1.0).abs() < 0.03;
+
+        assert!(close(mean(ServiceTime::Fixed { time_us: 2.0 }), 2.0));
+        assert!(close(
+            mean(ServiceTime::Exponential { mean_us: 10.0 }),
+            10.0
+        ));
+        assert!(close(
+            mean(ServiceTime::Bimodal {
+                modes_us: vec![1.0, 100.0],
+                weights: vec![90.0, 10.0]
+            }),
+            10.9
+        ));
+        assert!(close(
+            mean(ServiceTime::LogNormal {
+                mu: 0.0,
+                sigma: 0.5
+            }),
+            (0.125f64).exp()
+        ));
+        assert!(close(
+            mean(ServiceTime::Pareto {
+                scale_us: 1.0,
+                shape: 3.0
+            }),
+            1.5
+        ));
+        assert!(close(
+            mean(ServiceTime::Empirical {
+                values_us: vec![0.0, 10.0],
+                cdf: vec![0.0, 1.0]
+            }),
+            5.0
+        ));
+
+        let bounded = ServiceTime::BoundedPareto {
+            low_us: 1.0,
+            high_us: 100.0,
+            shape: 1.0,
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        assert!((0..10000)
+            .map(|_| bounded.sample(&mut rng))
+            .all(|s| (1.0..=100.0).contains(&s)));
+        mean(bounded);
+        mean(ServiceTime::BoundedPareto {
+            low_us: 1.0,
+            high_us: 100.0,
+            shape: 2.0,
+        });
+    }
+}
@@ -0,0 +1,117 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::Config;
+use super::report::DispatcherReport;
+use super::request::Request;
+
+use std::cmp::max;
+use std::collections::VecDeque;
+
+/// The dispatcher core of the Shinjuku policy. It owns a single global queue for the requests of
+/// all the tenants and hands them, one at a time, to the idle worker cores. The dispatcher is a
+/// core too; it spends `dispatch_cycles` on each request, so it saturates under a high load.
+pub struct Shinjuku {
+    // The requests waiting for a worker, in the order they arrived or were preempted.
+    queue: VecDeque<Box<Request>>,
+
+    // The request being handed to a worker, along with the index of that worker.
+    inflight: Option<(Box<Request>, usize)>,
+
+    // The CPU cycles spent on each request handed to a worker.
+    dispatch_cycles: u64,
+
+    // The number of requests handed to the workers; includes the preempted ones, every time.
+    dispatched: u64,
+
+    // The CPU cycles spent handing out the requests.
+    busy_cycles: u64,
+
+    // The longest the global queue has been.
+    max_queue: usize,
+}
+
+impl Shinjuku {
+    pub fn new(config: &Config) -> Shinjuku {
+        Shinjuku {
+            queue: VecDeque::with_capacity(1024),
+            inflight: None,
+            dispatch_cycles: config.dispatch_cycles,
+            dispatched: 0,
+            busy_cycles: 0,
+            max_queue: 0,
+        }
+    }
+
+    /// This method adds a new or a preempted request to the tail of the global queue.
+    pub fn enqueue(&mut self, req: Box<Request>) {
+        self.queue.push_back(req);
+        self.max_queue = max(self.max_queue, self.queue.len());
+    }
+
+    /// This method tells if the dispatcher can hand out a request; i.e. it is free and a
+    /// request is waiting.
+    pub fn is_ready(&self) -> bool {
+        self.inflight.is_none() && !self.queue.is_empty()
+    }
+
+    /// The tenant of the request at the head of the global queue, if any.
+    pub fn next_tenant(&self) -> Option<u32> {
+        self.queue.front().map(|req| req.get_tenant())
+    }
+
+    /// This method starts handing the request at the head of the queue to a worker.
+    ///
+    /// # Arguments
+    /// `worker`: The index of the idle worker core.
+    /// `now`: The current time-stamp on the global clock.
+    ///
+    /// # Return
+    /// The time-stamp at which the worker gets the request.
+    pub fn dispatch(&mut self, worker: usize, now: u64) -> u64 {
+        let req = self.queue.pop_front().expect("Dispatch without a request");
+        self.inflight = Some((req, worker));
+        self.dispatched += 1;
+        self.busy_cycles += self.dispatch_cycles;
+        now + self.dispatch_cycles
+    }
+
+    /// This method completes the hand-off started by `dispatch()`.
+    ///
+    /// # Return
+    /// The request and the index of the worker to run it.
+    pub fn complete(&mut self) -> (Box<Request>, usize) {
+        self.inflight.take().expect("No request in flight")
+    }
+
+    /// This method collects the counters of the dispatcher for the report.
+    ///
+    /// # Arguments
+    /// `now`: The current time-stamp on the global clock.
+    pub fn report(&self, now: u64) -> DispatcherReport {
+        let mut utilization = 0.0;
+        if now > 0 {
+            utilization = self.busy_cycles as f64 / now as f64;
+        }
+
+        DispatcherReport {
+            requests: self.dispatched,
+            utilization,
+            max_queue: self.max_queue,
+            busy_cycles: self.busy_cycles,
+            total_cycles: now,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::request::Request;
+use super::sched::{min_head, take, Context, Scheduler};
+
+/// Shortest remaining processing time first. The run-queue of each tenant is ordered by the
+/// remaining time of its requests, and the core runs the shortest request of all its tenants. A
+/// shorter request which arrives in the meantime waits for the time quanta to expire.
+pub struct Srpt {}
+
+impl Srpt {
+    pub fn new() -> Srpt {
+        Srpt {}
+    }
+}
+
+impl Scheduler for Srpt {
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        let mut tenant = ctx.tenants[index].borrow_mut();
+        tenant.insert_by(0, req, |r| r.remaining_time());
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        self.on_arrival(ctx, index, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        let index = min_head(ctx, 0, |_, r| r.remaining_time())?;
+        ctx.tenants[index].borrow_mut().pop_front(0)
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        match min_head(ctx, 0, |_, r| r.remaining_time()) {
+            Some(index) => take(ctx, index, 0, all),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cores::CoreType;
+    use crate::sched::test::{context, picks, tenants};
+
+    #[test]
+    fn test_srpt() {
+        // The shortest request of all the tenants runs first; a preempted request goes back by
+        // its remaining time.
+        let tenants = tenants(2);
+        let ctx = context(&tenants, CoreType::Small);
+        let mut srpt = Srpt::new();
+        for (t, time) in &[(1, 5.0), (1, 1.0), (2, 3.0), (2, 8.0)] {
+            srpt.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, *time)));
+        }
+        srpt.on_quantum_expiry(&ctx, 1, Box::new(Request::new(2, 0, 2.0)));
+
+        let stolen = srpt.steal_task(&ctx, false);
+        assert_eq!(stolen[0].remaining_time(), 1.0);
+        assert_eq!(
+            picks(&mut srpt, &ctx),
+            vec![(2, 2.0), (2, 3.0), (1, 5.0), (2, 8.0)]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::request::Request;
+use super::sched::{max_credit, min_head, run_time, take, Context, Scheduler};
+
+/// Stride scheduling over the tenants of a core. The pass of a tenant advances by the run time of
+/// each of its requests divided by its weight, and the tenant with the smallest pass runs next. A
+/// tenant which becomes runnable again starts from the pass of the core, so that it can't catch
+/// up on the time it was idle.
+pub struct Stride {
+    // The pass of the core; the smallest pass of the runnable tenants when it last picked.
+    pass: f64,
+
+    // The time quanta of the core in micro-seconds; a request runs for at most this long.
+    quanta_us: f64,
+}
+
+impl Stride {
+    pub fn new(quanta_us: f64) -> Stride {
+        Stride {
+            pass: 0.0,
+            quanta_us,
+        }
+    }
+}
+
+impl Scheduler for Stride {
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        let mut tenant = ctx.tenants[index].borrow_mut();
+        if tenant.len(0) == 0 {
+            tenant.credit = tenant.credit.max(self.pass);
+        }
+        tenant.push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_front(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        let index = min_head(ctx, 0, |t, _| t.credit)?;
+        let mut tenant = ctx.tenants[index].borrow_mut();
+        let req = tenant.pop_front(0)?;
+        self.pass = tenant.credit;
+        tenant.credit += run_time(&req, self.quanta_us) / tenant.weight;
+        Some(req)
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        // The tenant with the largest pass runs last.
+        match max_credit(ctx, 0) {
+            Some(index) => take(ctx, index, 0, all),
+            None => Vec::new(),
+        }
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_added(&mut self, ctx: &Context, index: usize) {
+        // The pass of the previous core means nothing here.
+        ctx.tenants[index].borrow_mut().credit = self.pass;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cores::CoreType;
+    use crate::sched::test::{context, picks, tenants};
+
+    #[test]
+    fn test_stride() {
+        // The second tenant has three times the weight, and three of every four picks.
+        let tenants = tenants(2);
+        tenants[1].borrow_mut().weight = 3.0;
+        let ctx = context(&tenants, CoreType::Small);
+        let mut stride = Stride::new(10.0);
+        for t in &[1, 1, 2, 2, 2, 2, 2, 2] {
+            stride.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, 1.0)));
+        }
+        let order: Vec<u32> = picks(&mut stride, &ctx).iter().map(|p| p.0).collect();
+        assert_eq!(order, vec![1, 2, 2, 2, 1, 2, 2, 2]);
+
+        // A tenant which becomes runnable again starts from the pass of the core.
+        for t in &[2, 2, 1] {
+            stride.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, 1.0)));
+        }
+        let order: Vec<u32> = picks(&mut stride, &ctx).iter().map(|p| p.0).collect();
+        assert_eq!(order, vec![2, 1, 2]);
+    }
+}
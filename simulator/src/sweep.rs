@@ -0,0 +1,269 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::{Config, Isolation, Policy};
+use super::cores::Simulator;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Sweep {
+    // The base configuration file; each point of the grid overrides some of its fields.
+    pub config: String,
+
+    // The directory to write the tables to.
+    pub out: String,
+
+    // The number of host threads used to run the points; zero uses all the host threads.
+    #[serde(default)]
+    pub jobs: usize,
+
+    // The number of tenants for each series.
+    pub num_tenants: Vec<u64>,
+
+    // The isolation mechanism for each series.
+    pub isolation: Vec<Isolation>,
+
+    // The scheduling policy for each series.
+    pub policy: Vec<Policy>,
+
+    // Preemption on and/or off for each series.
+    #[serde(default = "preemption")]
+    pub preemption: Vec<bool>,
+
+    // The request rates; each rate adds a row to every series.
+    pub req_rate: Vec<u64>,
+
+    // The `key=value` overrides applied to every point, e.g. to disable batching.
+    #[serde(default)]
+    pub set: Vec<String>,
+}
+
+fn preemption() -> Vec<bool> {
+    vec![true]
+}
+
+// The directory of the tables of an isolation mechanism, named as in HPTS.
+fn isolation_dir(isolation: &Isolation) -> &str {
+    match isolation {
+        Isolation::PageTableIsolation => "Paging",
+        Isolation::MpkIsolation => "MPK",
+        Isolation::VmfuncIsolation => "VMFunc",
+        _ => isolation.name(),
+    }
+}
+
+// One point of the grid, i.e. one simulator run.
+struct Point {
+    // The table this point belongs to.
+    series: usize,
+
+    // The row of this point in the table.
+    row: usize,
+
+    // The overrides for the base configuration.
+    overrides: Vec<String>,
+}
+
+impl Sweep {
+    /// This method reads the sweep description from a TOML file.
+    ///
+    /// # Arguments
+    /// `filename`: The path of the sweep file.
+    pub fn load(filename: &str) -> Result<Sweep, String> {
+        let mut contents = String::new();
+        File::open(filename)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("Failure reading sweep file {}: {}", filename, e))?;
+
+        toml::from_str(&contents)
+            .map_err(|e| format!("Failure parsing sweep file {}: {}", filename, e))
+    }
+
+    // The tables, one per combination of isolation, policy, preemption and number of tenants.
+    // Each table is written to `<out>/<Paging|MPK|VMFunc>/<num_tenants>_<PRE|NP>`, as in HPTS,
+    // with a `_<policy>` suffix when the sweep has several policies.
+    fn series(&self) -> Vec<(PathBuf, Vec<String>)> {
+        let mut series = Vec::new();
+        for isolation in &self.isolation {
+            for policy in &self.policy {
+                for preemption in &self.preemption {
+                    for tenants in &self.num_tenants {
+                        let mut name =
+                            format!("{}_{}", tenants, if *preemption { "PRE" } else { "NP" });
+                        if self.policy.len() > 1 {
+                            name = format!("{}_{:?}", name, policy);
+                        }
+                        let path = Path::new(&self.out)
+                            .join(isolation_dir(isolation))
+                            .join(name);
+
+                        let mut overrides = self.set.clone();
+                        overrides.push(format!("isolation=\"{}\"", isolation.name()));
+                        overrides.push(format!("policy=\"{:?}\"", policy));
+                        overrides.push(format!("preemption={}", preemption));
+                        overrides.push(format!("num_tenants={}", tenants));
+                        series.push((path, overrides));
+                    }
+                }
+            }
+        }
+        series
+    }
+
+    /// This method runs every point of the grid, in parallel, and writes one table per series.
+    pub fn run(&self) -> Result<(), String> {
+        let series = self.series();
+
+        // Load the configuration of every point upfront to catch the errors before any run.
+        let mut points = Vec::with_capacity(series.len() * self.req_rate.len());
+        for (s, (_, overrides)) in series.iter().enumerate() {
+            for (r, rate) in self.req_rate.iter().enumerate() {
+                let mut overrides = overrides.clone();
+                overrides.push(format!("req_rate={}", rate));
+                Config::load_with(&self.config, &overrides)?;
+                points.push(Point {
+                    series: s,
+                    row: r,
+                    overrides,
+                });
+            }
+        }
+
+        let mut jobs = self.jobs;
+        if jobs == 0 {
+            jobs = thread::available_parallelism().map_or(1, |n| n.get());
+        }
+
+        let total = points.len();
+        let points = Arc::new(Mutex::new(points));
+        let rows = Arc::new(Mutex::new(vec![
+            vec![String::new(); self.req_rate.len()];
+            series.len()
+        ]));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+
+        let mut children = Vec::with_capacity(jobs);
+        for _ in 0..jobs {
+            let points = Arc::clone(&points);
+            let rows = Arc::clone(&rows);
+            let errors = Arc::clone(&errors);
+            let filename = self.config.clone();
+
+            children.push(thread::spawn(move || loop {
+                let point = match points.lock().unwrap().pop() {
+                    Some(point) => point,
+                    None => return,
+                };
+
+                let simulator =
+                    Config::load_with(&filename, &point.overrides).and_then(Simulator::new);
+                let mut simulator = match simulator {
+                    Ok(simulator) => simulator,
+                    Err(e) => {
+                        errors
+                            .lock()
+                            .unwrap()
+                            .push(format!("{:?}: {}", point.overrides, e));
+                        continue;
+                    }
+                };
+                simulator.set_quiet();
+                simulator.start();
+
+                let row = simulator.row();
+                info!("{:?}: {}", point.overrides, row);
+                rows.lock().unwrap()[point.series][point.row] = row;
+            }));
+        }
+
+        for child in children {
+            child
+                .join()
+                .map_err(|_| "A simulator run panicked".to_string())?;
+        }
+        let errors = errors.lock().unwrap();
+        if !errors.is_empty() {
+            return Err(format!(
+                "{} of {} runs failed:\n{}",
+                errors.len(),
+                total,
+                errors.join("\n")
+            ));
+        }
+        info!("Finished {} runs", total);
+
+        let rows = rows.lock().unwrap();
+        for (s, (path, _)) in series.iter().enumerate() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failure creating directory {}: {}", dir.display(), e))?;
+            }
+            fs::write(path, rows[s].join("\n") + "\n")
+                .map_err(|e| format!("Failure writing {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_series() {
+        let sweep: Sweep = toml::from_str(
+            "config = \"config.toml\"
+            out = \"HPTS\"
+            num_tenants = [10, 100]
+            isolation = [\"PageTableIsolation\", \"VmfuncIsolation\"]
+            policy = [\"RoundRobin\"]
+            preemption = [true, false]
+            req_rate = [15625]",
+        )
+        .unwrap();
+
+        // The tables are named as in HPTS, and each point sets preemption.
+        let series = sweep.series();
+        let paths: Vec<PathBuf> = series.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(paths.len(), 8);
+        assert_eq!(paths[0], Path::new("HPTS/Paging/10_PRE"));
+        assert_eq!(paths[3], Path::new("HPTS/Paging/100_NP"));
+        assert_eq!(paths[5], Path::new("HPTS/VMFunc/100_PRE"));
+        assert!(series[3].1.contains(&"preemption=false".to_string()));
+    }
+
+    #[test]
+    fn test_run_errors() {
+        // The configurations load, but the simulators fail to read the trace.
+        let sweep: Sweep = toml::from_str(
+            "config = \"config.toml\"
+            out = \"/nonexistent\"
+            jobs = 2
+            num_tenants = [32]
+            isolation = [\"MpkIsolation\"]
+            policy = [\"RoundRobin\"]
+            req_rate = [15625, 31250]
+            set = [\"trace.path=\\\"/nonexistent\\\"\"]",
+        )
+        .unwrap();
+        let e = sweep.run().unwrap_err();
+        assert!(e.starts_with("2 of 2 runs failed"), "{}", e);
+    }
+}
@@ -13,35 +13,107 @@
  * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
  */
 
-use super::cores::CoreType;
+use super::config::Config;
 use super::request::Request;
-use super::sched::Scheduler;
+
+use std::collections::VecDeque;
 
 pub struct Tenant {
-    /// The scheduler is used to determine the schedule for the tasks for current tenant.
-    pub sched: Box<dyn Scheduler>,
+    /// The run-queues of the tenant; the scheduler of the core decides what each one holds.
+    pub rq: Vec<VecDeque<Box<Request>>>,
 
     // The ID of the current tenant.
-    pub tenant_id: u16,
+    pub tenant_id: u32,
+
+    // The service time, in micro-seconds, of the requests which arrived since the rebalancer
+    // last looked at this tenant.
+    pub demand_us: f64,
+
+    // The latency objective of the requests of this tenant in micro-seconds.
+    pub slo_us: f64,
+
+    // The priority class of this tenant; zero is the highest.
+    pub priority: u32,
+
+    // The share of the core of this tenant, relative to the other tenants of the core.
+    pub weight: f64,
+
+    // The state of the fair-share schedulers; the deficit of DRR in micro-seconds, the virtual
+    // finish time of WFQ or the pass of stride scheduling.
+    pub credit: f64,
 }
 
 impl Tenant {
-    pub fn new(tenant: u16, policy: Box<dyn Scheduler>) -> Tenant {
+    pub fn new(tenant: u32, config: &Config) -> Tenant {
         Tenant {
-            sched: policy,
+            rq: Vec::with_capacity(2),
             tenant_id: tenant,
+            demand_us: 0.0,
+            slo_us: config.tenant_slo_us(tenant),
+            priority: config.tenant_priority(tenant),
+            weight: config.tenant_weight(tenant),
+            credit: 0.0,
         }
     }
 
-    pub fn add_request(&mut self, rdtsc: u64, task_time: f64) {
-        self.sched.create_task(rdtsc, task_time, self.tenant_id);
+    /// This method adds a request to the tail of a run-queue.
+    pub fn push_back(&mut self, queue: usize, req: Box<Request>) {
+        self.queue(queue).push_back(req);
+    }
+
+    /// This method adds a request to the head of a run-queue.
+    pub fn push_front(&mut self, queue: usize, req: Box<Request>) {
+        self.queue(queue).push_front(req);
     }
 
-    pub fn get_request(&mut self, coretype: CoreType) -> Option<Box<Request>> {
-        self.sched.pick_next_task(coretype)
+    /// This method adds a request to a run-queue ordered by the given key; after the requests
+    /// with the same key.
+    ///
+    /// # Arguments
+    /// `queue`: The run-queue; its requests are in increasing order of `key`.
+    /// `req`: The request to add.
+    /// `key`: The key of a request; e.g. its remaining time.
+    pub fn insert_by<K: PartialOrd>(
+        &mut self,
+        queue: usize,
+        req: Box<Request>,
+        key: impl Fn(&Request) -> K,
+    ) {
+        let rq = self.queue(queue);
+        let k = key(&req);
+        // Most requests go to the tail; e.g. the new ones by deadline.
+        let position = rq.iter().rposition(|r| key(r) <= k).map_or(0, |p| p + 1);
+        rq.insert(position, req);
     }
 
-    pub fn enqueue_task(&mut self, req: Box<Request>) {
-        self.sched.enqueue_task(req);
+    /// The request at the head of a run-queue, if there is any.
+    pub fn front(&self, queue: usize) -> Option<&Request> {
+        self.rq
+            .get(queue)
+            .and_then(|rq| rq.front())
+            .map(|req| &**req)
+    }
+
+    /// This method removes the request at the head of a run-queue, if there is any.
+    pub fn pop_front(&mut self, queue: usize) -> Option<Box<Request>> {
+        self.rq.get_mut(queue).and_then(|rq| rq.pop_front())
+    }
+
+    /// The number of requests in a run-queue.
+    pub fn len(&self, queue: usize) -> usize {
+        self.rq.get(queue).map_or(0, |rq| rq.len())
+    }
+
+    /// The number of runnable requests in all the run-queues; they move with the tenant.
+    pub fn queued(&self) -> usize {
+        self.rq.iter().map(|rq| rq.len()).sum()
+    }
+
+    // The run-queue with the given index; the run-queues are created on their first use.
+    fn queue(&mut self, queue: usize) -> &mut VecDeque<Box<Request>> {
+        while self.rq.len() <= queue {
+            self.rq.push(VecDeque::with_capacity(32));
+        }
+        &mut self.rq[queue]
     }
 }
@@ -0,0 +1,166 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::Config;
+
+use std::cmp::min;
+
+/// The cores of the server; their SMT siblings, NUMA nodes and sockets. The cores are numbered
+/// like the tenants: consecutive cores are SMT siblings, and the physical cores are split in
+/// order over the NUMA nodes, whose nodes are split in order over the sockets.
+pub struct Topology {
+    // The number of hardware threads of each physical core.
+    smt: usize,
+
+    // The number of NUMA nodes of each socket.
+    numa_nodes: usize,
+
+    // The NUMA node of each core.
+    nodes: Vec<usize>,
+
+    // The NUMA node which holds the memory of each tenant, indexed by tenant-id - 1; the node of
+    // the core the tenant is first placed on.
+    homes: Vec<u32>,
+
+    // The CPU cycles a switch costs extra when the memory of the tenant is on another NUMA node
+    // of the same socket, and on another socket.
+    remote_numa_cycles: u64,
+    remote_socket_cycles: u64,
+}
+
+impl Topology {
+    /// This method lays the cores out over the topology of the configuration.
+    ///
+    /// # Arguments
+    /// `config`: The simulator configuration; it must be valid.
+    /// `owner`: The core which first runs each tenant, indexed by tenant-id - 1.
+    pub fn new(config: &Config, owner: &[usize]) -> Topology {
+        let topology = &config.topology;
+        let smt = topology.smt as usize;
+        let num_physical = config.num_cores as usize / smt;
+        let num_nodes = (topology.sockets * topology.numa_nodes) as usize;
+        let per_node = num_physical / num_nodes;
+
+        // The last node also takes the remainder of the physical cores.
+        let nodes: Vec<usize> = (0..config.num_cores as usize)
+            .map(|core| min(core / smt / per_node, num_nodes - 1))
+            .collect();
+        let homes = owner.iter().map(|core| nodes[*core] as u32).collect();
+
+        Topology {
+            smt,
+            numa_nodes: topology.numa_nodes as usize,
+            nodes,
+            homes,
+            remote_numa_cycles: topology.remote_numa_cycles,
+            remote_socket_cycles: topology.remote_socket_cycles,
+        }
+    }
+
+    /// The NUMA node of the core.
+    pub fn node(&self, core: usize) -> usize {
+        self.nodes[core]
+    }
+
+    /// The socket of the NUMA node.
+    pub fn socket(&self, node: usize) -> usize {
+        node / self.numa_nodes
+    }
+
+    /// This method tells how far apart two cores are; the cores closer to an idle core are the
+    /// first ones it steals from.
+    ///
+    /// # Return
+    /// 0 for SMT siblings, 1 on the same NUMA node, 2 on the same socket and 3 across sockets.
+    pub fn distance(&self, a: usize, b: usize) -> usize {
+        let (node_a, node_b) = (self.nodes[a], self.nodes[b]);
+        if a / self.smt == b / self.smt {
+            0
+        } else if node_a == node_b {
+            1
+        } else if self.socket(node_a) == self.socket(node_b) {
+            2
+        } else {
+            3
+        }
+    }
+
+    /// This method returns the extra CPU cycles a core spends on a switch to a tenant; the first
+    /// accesses of the tenant go to the memory of its NUMA node.
+    ///
+    /// # Arguments
+    /// `core`: The core which switches to the tenant.
+    /// `tenant`: The tenant-id.
+    ///
+    /// # Return
+    /// The CPU cycles; zero if the memory of the tenant is on the NUMA node of the core.
+    pub fn switch_cycles(&self, core: usize, tenant: u32) -> u64 {
+        let (node, home) = (self.nodes[core], self.homes[tenant as usize - 1] as usize);
+        if node == home {
+            0
+        } else if self.socket(node) == self.socket(home) {
+            self.remote_numa_cycles
+        } else {
+            self.remote_socket_cycles
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_topology() {
+        // 2 sockets of 2 NUMA nodes, with 2 threads per physical core; 3 physical cores per node.
+        let mut config: Config = toml::from_str(
+            "num_cores = 24
+            large_cores = 0
+            num_tenants = 4
+            tenant_skew = 0.9
+            num_reqs = 2000
+            num_resps = 1000
+            req_rate = 1000000
+            batching = true
+            isolation = \"NoIsolation\"
+            distribution = \"Zipf\"
+            policy = \"RoundRobin\"",
+        )
+        .unwrap();
+        config.topology.sockets = 2;
+        config.topology.numa_nodes = 2;
+        config.topology.smt = 2;
+        let topology = Topology::new(&config, &[0, 6, 12, 23]);
+
+        assert_eq!(topology.node(5), 0);
+        assert_eq!(topology.node(6), 1);
+        assert_eq!(topology.node(23), 3);
+        assert_eq!(topology.distance(4, 5), 0);
+        assert_eq!(topology.distance(3, 5), 1);
+        assert_eq!(topology.distance(0, 6), 2);
+        assert_eq!(topology.distance(0, 12), 3);
+
+        // Tenant 2 lives on node 1; the other node of its socket is closer than the other socket.
+        assert_eq!(topology.switch_cycles(7, 2), 0);
+        assert_eq!(
+            topology.switch_cycles(0, 2),
+            config.topology.remote_numa_cycles
+        );
+        assert_eq!(
+            topology.switch_cycles(12, 2),
+            config.topology.remote_socket_cycles
+        );
+    }
+}
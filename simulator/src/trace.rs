@@ -0,0 +1,257 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::config::{TenantMap, TraceConfig, TraceFormat};
+use super::cycles::Clock;
+
+use std::collections::HashMap;
+use std::fs;
+
+/// The binary traces start with this magic; then follow 16 byte records of the little-endian
+/// arrival time in nano-seconds (u64), the tenant-id (u32) and the service time in nano-seconds
+/// (u32).
+pub const BINARY_MAGIC: &[u8; 8] = b"SSTRACE1";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceRecord {
+    // The arrival time of the request; in nano-seconds in the trace file and in CPU cycles once
+    // the trace is prepared for the simulator.
+    pub time: u64,
+
+    // The tenant which issued the request.
+    pub tenant: u64,
+
+    // The service time of the request in micro-seconds.
+    pub service_us: f64,
+}
+
+/// This function reads a CSV trace; each line has the arrival time in micro-seconds, the
+/// tenant-id and the service time in micro-seconds. A header line and lines starting with `#`
+/// are skipped. The times can't be negative, NaN or infinite.
+///
+/// # Arguments
+/// `contents`: The contents of the trace file.
+pub fn parse_csv(contents: &str) -> Result<Vec<TraceRecord>, String> {
+    let mut records = Vec::new();
+    for (num, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let parsed = match fields.as_slice() {
+            [time, tenant, service] => (
+                time.parse::<f64>(),
+                tenant.parse::<u64>(),
+                service.parse::<f64>(),
+            ),
+            _ => return Err(format!("line {}: expected 3 fields", num + 1)),
+        };
+
+        match parsed {
+            (Ok(time), Ok(_), Ok(service))
+                if !time.is_finite() || time < 0.0 || !service.is_finite() || service < 0.0 =>
+            {
+                return Err(format!(
+                    "line {}: the times must be finite and not negative",
+                    num + 1
+                ))
+            }
+            (Ok(time), Ok(tenant), Ok(service)) => records.push(TraceRecord {
+                time: (time * 1e3) as u64,
+                tenant,
+                service_us: service,
+            }),
+            // Allow a header on the first line.
+            _ if records.is_empty() && num == 0 => {}
+            _ => return Err(format!("line {}: invalid record '{}'", num + 1, line)),
+        }
+    }
+    Ok(records)
+}
+
+/// This function reads a binary trace; see `BINARY_MAGIC` for the format.
+///
+/// # Arguments
+/// `contents`: The contents of the trace file.
+pub fn parse_binary(contents: &[u8]) -> Result<Vec<TraceRecord>, String> {
+    if contents.len() < BINARY_MAGIC.len() || &contents[..BINARY_MAGIC.len()] != BINARY_MAGIC {
+        return Err("not a binary trace; bad magic".to_string());
+    }
+
+    let body = &contents[BINARY_MAGIC.len()..];
+    if !body.len().is_multiple_of(16) {
+        return Err(format!("truncated record at byte {}", body.len() / 16 * 16));
+    }
+
+    let mut records = Vec::with_capacity(body.len() / 16);
+    for chunk in body.chunks(16) {
+        let mut time = [0; 8];
+        let mut tenant = [0; 4];
+        let mut service = [0; 4];
+        time.copy_from_slice(&chunk[0..8]);
+        tenant.copy_from_slice(&chunk[8..12]);
+        service.copy_from_slice(&chunk[12..16]);
+        records.push(TraceRecord {
+            time: u64::from_le_bytes(time),
+            tenant: u32::from_le_bytes(tenant) as u64,
+            service_us: u32::from_le_bytes(service) as f64 / 1e3,
+        });
+    }
+    Ok(records)
+}
+
+/// This function encodes the records in the binary trace format.
+///
+/// # Arguments
+/// `records`: The records with their arrival time in nano-seconds.
+///
+/// # Return
+/// The binary trace, or an error if a tenant-id or a service time doesn't fit in 32 bits.
+pub fn to_binary(records: &[TraceRecord]) -> Result<Vec<u8>, String> {
+    let mut contents = Vec::with_capacity(BINARY_MAGIC.len() + records.len() * 16);
+    contents.extend_from_slice(BINARY_MAGIC);
+    for (i, r) in records.iter().enumerate() {
+        if r.tenant > u32::MAX as u64 {
+            return Err(format!(
+                "record {}: tenant {} doesn't fit in u32",
+                i + 1,
+                r.tenant
+            ));
+        }
+        let service_ns = (r.service_us * 1e3).round();
+        if !(0.0..=u32::MAX as f64).contains(&service_ns) {
+            return Err(format!(
+                "record {}: service time {}us doesn't fit in u32 nano-seconds",
+                i + 1,
+                r.service_us
+            ));
+        }
+
+        contents.extend_from_slice(&r.time.to_le_bytes());
+        contents.extend_from_slice(&(r.tenant as u32).to_le_bytes());
+        contents.extend_from_slice(&(service_ns as u32).to_le_bytes());
+    }
+    Ok(contents)
+}
+
+/// This function reads the trace in the configuration and prepares it for the simulator; the
+/// arrival times are scaled, converted to CPU cycles and made relative to the first request, and
+/// the tenant-ids are mapped onto `1..=num_tenants`.
+///
+/// # Arguments
+/// `config`: The trace section of the configuration.
+/// `num_tenants`: The number of tenants in the simulation.
+/// `clock`: The virtual clock of the simulator.
+///
+/// # Return
+/// The records sorted by their arrival time.
+pub fn load(
+    config: &TraceConfig,
+    num_tenants: u64,
+    clock: &Clock,
+) -> Result<Vec<TraceRecord>, String> {
+    let contents = fs::read(&config.path)
+        .map_err(|e| format!("Failure reading trace {}: {}", config.path, e))?;
+    let mut records = match config.format {
+        TraceFormat::Csv => parse_csv(&String::from_utf8_lossy(&contents)),
+        TraceFormat::Binary => parse_binary(&contents),
+    }
+    .map_err(|e| format!("Failure parsing trace {}: {}", config.path, e))?;
+
+    records.sort_by_key(|r| r.time);
+    let start = records.first().map_or(0, |r| r.time);
+    let cycles_per_ns = clock.cycles_per_second() as f64 / 1e9;
+
+    let mut ids = HashMap::new();
+    for r in records.iter_mut() {
+        r.time = ((r.time - start) as f64 * config.time_scale * cycles_per_ns) as u64;
+        r.tenant = match config.tenant_map {
+            TenantMap::Modulo => r.tenant % num_tenants,
+            TenantMap::Dense => {
+                let next = ids.len() as u64;
+                *ids.entry(r.tenant).or_insert(next) % num_tenants
+            }
+        } + 1;
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_csv_binary_roundtrip() {
+        let csv = "time_us,tenant,service_us\n0.5,7,1.25\n# comment\n\n2,3,1000\n";
+        let records = parse_csv(csv).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                TraceRecord {
+                    time: 500,
+                    tenant: 7,
+                    service_us: 1.25
+                },
+                TraceRecord {
+                    time: 2000,
+                    tenant: 3,
+                    service_us: 1000.0
+                },
+            ]
+        );
+        assert_eq!(
+            parse_binary(&to_binary(&records).unwrap()).unwrap(),
+            records
+        );
+        assert!(parse_csv("1,2\n").is_err());
+        assert!(parse_binary(b"SSTRACE1\x01").is_err());
+    }
+
+    #[test]
+    fn test_csv_invalid_times() {
+        for line in &[
+            "1,2,-1", "1,2,NaN", "1,2,inf", "-1,2,1", "NaN,2,1", "inf,2,1",
+        ] {
+            let e = parse_csv(&format!("0,1,1\n{}\n", line)).unwrap_err();
+            assert!(e.starts_with("line 2:"), "{}: {}", line, e);
+        }
+    }
+
+    #[test]
+    fn test_binary_overflow() {
+        let record = TraceRecord {
+            time: 0,
+            tenant: 1,
+            service_us: 1.0,
+        };
+        assert!(to_binary(&[record]).is_ok());
+
+        let tenant = TraceRecord {
+            tenant: u32::MAX as u64 + 1,
+            ..record
+        };
+        assert!(to_binary(&[record, tenant])
+            .unwrap_err()
+            .starts_with("record 2:"));
+
+        let service = TraceRecord {
+            service_us: 5e6,
+            ..record
+        };
+        assert!(to_binary(&[service]).unwrap_err().starts_with("record 1:"));
+    }
+}
@@ -0,0 +1,120 @@
+/* Copyright (c) 2019 University of Utah
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR(S) DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL AUTHORS BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::request::Request;
+use super::sched::{max_credit, min_head, run_time, take, Context, Scheduler};
+use super::tenant::Tenant;
+
+/// Weighted fair queuing over the tenants of a core. The request at the head of the run-queue of
+/// a tenant starts, in virtual time, once the previous request of the tenant finished, or at the
+/// virtual time of the core if the tenant was idle; it finishes after its run time divided by the
+/// weight of the tenant. The request with the earliest virtual finish time runs first. The
+/// virtual time of the core is the start time of the last request picked, as in start-time fair
+/// queuing.
+pub struct Wfq {
+    // The virtual time of the core.
+    vtime: f64,
+
+    // The time quanta of the core in micro-seconds; a request runs for at most this long.
+    quanta_us: f64,
+}
+
+impl Wfq {
+    pub fn new(quanta_us: f64) -> Wfq {
+        Wfq {
+            vtime: 0.0,
+            quanta_us,
+        }
+    }
+}
+
+impl Scheduler for Wfq {
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_arrival(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        // An idle tenant can't catch up on the time it was idle.
+        let mut tenant = ctx.tenants[index].borrow_mut();
+        if tenant.len(0) == 0 {
+            tenant.credit = tenant.credit.max(self.vtime);
+        }
+        tenant.push_back(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_quantum_expiry(&mut self, ctx: &Context, index: usize, req: Box<Request>) {
+        ctx.tenants[index].borrow_mut().push_front(0, req);
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn pick_next_task(&mut self, ctx: &Context) -> Option<Box<Request>> {
+        let quanta_us = self.quanta_us;
+        let finish = |t: &Tenant, r: &Request| t.credit + run_time(r, quanta_us) / t.weight;
+        let index = min_head(ctx, 0, finish)?;
+
+        let mut tenant = ctx.tenants[index].borrow_mut();
+        let req = tenant.pop_front(0)?;
+        self.vtime = tenant.credit;
+        tenant.credit = finish(&tenant, &req);
+        Some(req)
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn steal_task(&mut self, ctx: &Context, all: bool) -> Vec<Box<Request>> {
+        // The tenant which is the furthest ahead in virtual time.
+        match max_credit(ctx, 0) {
+            Some(index) => take(ctx, index, 0, all),
+            None => Vec::new(),
+        }
+    }
+
+    // Lookup the `Scheduler` trait for documentation on this method.
+    fn on_tenant_added(&mut self, ctx: &Context, index: usize) {
+        // The virtual time of the previous core means nothing here.
+        ctx.tenants[index].borrow_mut().credit = self.vtime;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cores::CoreType;
+    use crate::sched::test::{context, picks, tenants};
+
+    #[test]
+    fn test_wfq() {
+        // The second tenant has three times the weight, and three of the first four picks.
+        let tenants = tenants(3);
+        tenants[1].borrow_mut().weight = 3.0;
+        let ctx = context(&tenants, CoreType::Small);
+        let mut wfq = Wfq::new(10.0);
+        for t in &[1, 1, 2, 2, 2, 2] {
+            wfq.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, 1.0)));
+        }
+        let order: Vec<u32> = picks(&mut wfq, &ctx).iter().map(|p| p.0).collect();
+        assert_eq!(order, vec![2, 2, 1, 2, 2, 1]);
+
+        // The third tenant was idle so far; it starts from the virtual time of the core, and
+        // takes turns with the first tenant instead of catching up.
+        let mut wfq = Wfq::new(10.0);
+        for _i in 0..4 {
+            wfq.on_arrival(&ctx, 0, Box::new(Request::new(1, 0, 1.0)));
+        }
+        picks(&mut wfq, &ctx);
+        for t in &[1, 1, 3, 3] {
+            wfq.on_arrival(&ctx, *t as usize - 1, Box::new(Request::new(*t, 0, 1.0)));
+        }
+        let order: Vec<u32> = picks(&mut wfq, &ctx).iter().map(|p| p.0).collect();
+        assert_eq!(order, vec![3, 1, 3, 1]);
+    }
+}